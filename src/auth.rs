@@ -0,0 +1,467 @@
+use crate::config::{AppConfig, ProviderKind};
+use crate::github_auth::Endpoints;
+use anyhow::{Context, Result, bail};
+use reqwest::blocking::{Client, RequestBuilder};
+use reqwest::StatusCode;
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use std::thread;
+use std::time::Duration;
+
+pub mod bitbucket;
+pub mod gitea;
+pub mod gitlab;
+pub mod loopback;
+
+pub use crate::github_auth::GitHub;
+pub use bitbucket::Bitbucket;
+pub use gitea::Gitea;
+pub use gitlab::GitLab;
+pub use loopback::AuthCodeClient;
+
+/// User-Agent sent to every provider API. / 各プロバイダーの API に送信する User-Agent。
+pub const USER_AGENT: &str = "gas-cli";
+
+/// Device authorization obtained at the start of a device flow. / デバイスフローの開始時に取得するデバイス認可。
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct DeviceAuthorization {
+    /// Identifier for polling / ポーリング用の識別子
+    pub device_code: String,
+    /// Code the user enters in the browser / ユーザーがブラウザで入力するコード
+    pub user_code: String,
+    /// URL the user accesses / ユーザーがアクセスする URL
+    pub verification_uri: String,
+    /// Polling interval in seconds / ポーリング間隔（秒）
+    #[serde(default = "default_interval")]
+    pub interval: u64,
+}
+
+fn default_interval() -> u64 {
+    5
+}
+
+/// How the user signs in to a provider. / プロバイダーへのサインイン方法。
+#[derive(Debug, Clone, PartialEq)]
+pub enum Flow {
+    /// OAuth device flow: the user enters `user_code` in the browser, then call `AuthProvider::poll`.
+    /// OAuth デバイスフロー: ユーザーがブラウザで `user_code` を入力した後、`AuthProvider::poll` を呼び出します。
+    Device(DeviceAuthorization),
+    /// The user creates an app password (or access token) at `url` and enters it together with the username.
+    /// ユーザーが `url` でアプリパスワード（またはアクセストークン）を作成し、ユーザー名とともに入力します。
+    AppPassword { url: String },
+}
+
+/// Access token issued by a provider. / プロバイダーが発行したアクセストークン。
+///
+/// Expiring tokens come with a refresh token and lifetimes; otherwise these are `None`.
+/// 有効期限付きのトークンではリフレッシュトークンと有効期間が含まれます。それ以外ではこれらは `None` です。
+#[derive(Debug, Clone, PartialEq)]
+pub struct AccessToken {
+    /// Access token / アクセストークン
+    pub access_token: String,
+    /// Lifetime of the access token in seconds / アクセストークンの有効期間（秒）
+    pub expires_in: Option<u64>,
+    /// Refresh token / リフレッシュトークン
+    pub refresh_token: Option<String>,
+    /// Lifetime of the refresh token in seconds / リフレッシュトークンの有効期間（秒）
+    pub refresh_token_expires_in: Option<u64>,
+}
+
+/// Identity of the owner of a credential. / 資格情報の所有者の情報。
+#[derive(Debug, Clone, PartialEq)]
+pub struct Identity {
+    /// Login name (the username used for Git) / ログイン名（Git で使用するユーザー名）
+    pub login: String,
+    /// Display name (if set) / 表示名（設定されている場合）
+    pub name: Option<String>,
+    /// Email to use for commits (if known) / コミットに使用するメールアドレス（わかる場合）
+    pub email: Option<String>,
+}
+
+/// Result of validating a credential that the provider accepted. / プロバイダーが受け付けた資格情報の検証結果。
+#[derive(Debug, Clone, PartialEq)]
+pub struct TokenInfo {
+    /// Owner of the credential / 資格情報の所有者
+    pub identity: Identity,
+    /// Granted scopes (`None` if the provider does not report them) / 付与されたスコープ（プロバイダーが返さない場合は `None`）
+    pub scopes: Option<Vec<String>>,
+}
+
+/// Interface for signing in to a Git hosting service and managing the issued credentials.
+/// Git ホスティングサービスへのサインインと、発行された資格情報の管理を抽象化するトレイト。
+pub trait AuthProvider {
+    /// Display name of the provider. / プロバイダーの表示名。
+    fn name(&self) -> &'static str;
+
+    /// Start signing in. / サインインを開始します。
+    fn start(&self) -> Result<Flow>;
+
+    /// OAuth client for the browser sign-in with PKCE and a loopback redirect, if the provider supports it.
+    /// プロバイダーが対応している場合、PKCE とループバックリダイレクトによるブラウザでのサインインの OAuth クライアントを返します。
+    fn authorization_code(&self) -> Option<AuthCodeClient> {
+        None
+    }
+
+    /// Scopes requested by the browser sign-in (empty if gas does not request any).
+    /// ブラウザでのサインインで要求するスコープ（gas が要求しない場合は空）。
+    fn scopes(&self) -> &[String] {
+        &[]
+    }
+
+    /// Wait until the user approves the device authorization and return the issued token.
+    /// ユーザーがデバイス認可を承認するまで待機し、発行されたトークンを返します。
+    fn poll(&self, device: &DeviceAuthorization) -> Result<AccessToken>;
+
+    /// Fetch the owner of a credential. `login` is required by providers that use basic authentication.
+    /// 資格情報の所有者を取得します。`login` は Basic 認証を使用するプロバイダーで必要です。
+    fn identity(&self, login: Option<&str>, secret: &str) -> Result<Identity>;
+
+    /// Check a credential. Returns `None` if the provider rejected it.
+    /// 資格情報を検証します。プロバイダーが拒否した場合は `None` を返します。
+    fn validate(&self, login: Option<&str>, secret: &str) -> Result<Option<TokenInfo>>;
+
+    /// Exchange a refresh token for a new token pair. / リフレッシュトークンを新しいトークンの組と交換します。
+    fn refresh(&self, refresh_token: &str) -> Result<AccessToken>;
+
+    /// Revoke a token at the provider. / プロバイダー側でトークンを失効させます。
+    fn revoke(&self, secret: &str) -> Result<()>;
+
+    /// Whether `revoke` can revoke a token through the API with the current settings.
+    /// 現在の設定で `revoke` が API を使用してトークンを失効させられるかどうか。
+    fn can_revoke(&self) -> bool {
+        true
+    }
+
+    /// Settings page where the user can revoke the authorization manually.
+    /// 利用者が手動で認可を取り消せる設定画面の URL。
+    fn revocation_url(&self) -> String;
+}
+
+impl<T: AuthProvider + ?Sized> AuthProvider for Box<T> {
+    fn name(&self) -> &'static str {
+        (**self).name()
+    }
+
+    fn start(&self) -> Result<Flow> {
+        (**self).start()
+    }
+
+    fn authorization_code(&self) -> Option<AuthCodeClient> {
+        (**self).authorization_code()
+    }
+
+    fn scopes(&self) -> &[String] {
+        (**self).scopes()
+    }
+
+    fn poll(&self, device: &DeviceAuthorization) -> Result<AccessToken> {
+        (**self).poll(device)
+    }
+
+    fn identity(&self, login: Option<&str>, secret: &str) -> Result<Identity> {
+        (**self).identity(login, secret)
+    }
+
+    fn validate(&self, login: Option<&str>, secret: &str) -> Result<Option<TokenInfo>> {
+        (**self).validate(login, secret)
+    }
+
+    fn refresh(&self, refresh_token: &str) -> Result<AccessToken> {
+        (**self).refresh(refresh_token)
+    }
+
+    fn revoke(&self, secret: &str) -> Result<()> {
+        (**self).revoke(secret)
+    }
+
+    fn can_revoke(&self) -> bool {
+        (**self).can_revoke()
+    }
+
+    fn revocation_url(&self) -> String {
+        (**self).revocation_url()
+    }
+}
+
+/// Build the provider for `host` from the `[providers."<host>"]` section of the settings.
+/// 設定の `[providers."<host>"]` セクションから `host` のプロバイダーを作成します。
+///
+/// # Errors
+/// プロバイダーに必要な設定（GitHub Enterprise Server のクライアント ID 等）が不足している場合にエラーを返します。
+pub fn provider_for_host(config: &AppConfig, host: &str) -> Result<Box<dyn AuthProvider>> {
+    provider_with_scopes(config, host, None)
+}
+
+/// Like `provider_for_host`, but request `scopes` (if given) instead of the configured or built-in default.
+/// `provider_for_host` と同様ですが、`scopes` を指定した場合は設定や組み込みの既定値の代わりにそのスコープを要求します。
+///
+/// # Errors
+/// プロバイダーに必要な設定（GitHub Enterprise Server のクライアント ID 等）が不足している場合にエラーを返します。
+pub fn provider_with_scopes(config: &AppConfig, host: &str, scopes: Option<&[String]>) -> Result<Box<dyn AuthProvider>> {
+    let (kind, mut settings) = config.provider(host);
+    if let Some(scopes) = scopes {
+        settings.scopes = Some(scopes.to_vec());
+    }
+    Ok(match kind {
+        ProviderKind::GitHub => Box::new(GitHub::new(Endpoints::for_host(config, host)?, &settings)),
+        ProviderKind::GitLab => Box::new(GitLab::new(host, &settings)),
+        ProviderKind::Gitea => Box::new(Gitea::new(host, &settings)),
+        ProviderKind::Bitbucket => Box::new(Bitbucket::new(host, &settings)),
+    })
+}
+
+/// Remove a trailing slash from a configured URL. / 設定された URL の末尾のスラッシュを取り除きます。
+pub(crate) fn trim_url(url: &str) -> String {
+    url.trim_end_matches('/').to_string()
+}
+
+/// Token endpoint response (RFC 6749). / トークンエンドポイントの応答（RFC 6749）。
+#[derive(Deserialize)]
+pub(crate) struct TokenResponse {
+    access_token: Option<String>,
+    expires_in: Option<u64>,
+    refresh_token: Option<String>,
+    refresh_token_expires_in: Option<u64>,
+    error: Option<String>,
+    error_description: Option<String>,
+}
+
+impl TokenResponse {
+    fn into_token(self) -> Option<AccessToken> {
+        Some(AccessToken {
+            access_token: self.access_token?,
+            expires_in: self.expires_in,
+            refresh_token: self.refresh_token,
+            refresh_token_expires_in: self.refresh_token_expires_in,
+        })
+    }
+}
+
+/// Post a form to a token endpoint. Error responses carry a JSON body with `error`, so it is parsed regardless of the status.
+/// トークンエンドポイントへフォームを送信します。エラー応答も `error` を含む JSON のため、ステータスにかかわらず解析します。
+fn post_token_form(client: &Client, url: &str, form: &[(&str, &str)]) -> Result<TokenResponse> {
+    let res = client.post(url)
+        .header("Accept", "application/json")
+        .header("User-Agent", USER_AGENT)
+        .form(form)
+        .send()
+        .with_context(|| format!("Failed to connect to {}", url))?;
+    let status = res.status();
+    let text = res.text().unwrap_or_default();
+    match serde_json::from_str::<TokenResponse>(&text) {
+        Ok(body) if status.is_success() || body.error.is_some() => Ok(body),
+        _ => bail!("Token Error: {} - {}", status, text),
+    }
+}
+
+/// Poll a token endpoint with the device code (RFC 8628) until the user approves.
+/// ユーザーが承認するまで、デバイスコードでトークンエンドポイントをポーリングします（RFC 8628）。
+///
+/// # Errors
+/// タイムアウトした場合や、ユーザーが明示的に拒否した場合にエラーを返します。
+pub(crate) fn poll_device_token(url: &str, client_id: &str, device: &DeviceAuthorization) -> Result<AccessToken> {
+    let client = Client::new();
+    let wait_time = Duration::from_secs(device.interval + 1);
+
+    for _ in 0..100 {
+        thread::sleep(wait_time);
+        let body = post_token_form(&client, url, &[
+            ("client_id", client_id),
+            ("device_code", &device.device_code),
+            ("grant_type", "urn:ietf:params:oauth:grant-type:device_code"),
+        ])?;
+        if let Some(err) = body.error {
+            if err == "authorization_pending" { continue; }
+            else if err == "slow_down" { thread::sleep(Duration::from_secs(5)); continue; }
+            else { bail!("Authorization error: {}", err); }
+        }
+        if let Some(token) = body.into_token() {
+            return Ok(token);
+        }
+    }
+    bail!("Timeout waiting for authorization.");
+}
+
+/// Exchange a refresh token at a token endpoint. / トークンエンドポイントでリフレッシュトークンを交換します。
+///
+/// `client_secret` is sent when configured; GitHub requires it for the refresh grant.
+/// `client_secret` は設定されている場合に送信します（GitHub ではリフレッシュに必須です）。
+///
+/// # Errors
+/// リフレッシュトークンが無効・期限切れの場合や、通信に失敗した場合にエラーを返します。
+pub(crate) fn refresh_token(url: &str, client_id: &str, client_secret: Option<&str>, refresh_token: &str) -> Result<AccessToken> {
+    let mut form = vec![
+        ("client_id", client_id),
+        ("grant_type", "refresh_token"),
+        ("refresh_token", refresh_token),
+    ];
+    if let Some(secret) = client_secret {
+        form.push(("client_secret", secret));
+    }
+    let body = post_token_form(&Client::new(), url, &form)?;
+    if let Some(err) = body.error {
+        bail!("Token refresh error: {} {}", err, body.error_description.unwrap_or_default());
+    }
+    body.into_token().context("Token response did not contain an access token")
+}
+
+/// Send an authenticated API request and parse the JSON response with the scopes from the `X-OAuth-Scopes` header.
+/// Returns `None` if the credential was rejected (401, or 403 other than a rate limit).
+/// 認証付きの API リクエストを送信し、JSON の応答と `X-OAuth-Scopes` ヘッダーのスコープを返します。
+/// 資格情報が拒否された場合（401、またはレート制限以外の 403）は `None` を返します。
+///
+/// # Errors
+/// Returns an error if the rate limit is exceeded (403 with `X-RateLimit-Remaining: 0`) or on other failures.
+/// レート制限を超えた場合（`X-RateLimit-Remaining: 0` の 403）やその他の失敗ではエラーを返します。
+pub(crate) fn fetch_authorized<T: DeserializeOwned>(request: RequestBuilder) -> Result<Option<(T, Option<Vec<String>>)>> {
+    let res = request
+        .header("User-Agent", USER_AGENT)
+        .header("Accept", "application/json")
+        .send()
+        .context("Failed to connect to the provider API")?;
+    if res.status() == StatusCode::FORBIDDEN
+        && res.headers().get("X-RateLimit-Remaining").is_some_and(|value| value.as_bytes() == b"0")
+    {
+        bail!("API rate limit exceeded: {}", res.status());
+    }
+    if matches!(res.status(), StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN) {
+        return Ok(None);
+    }
+    if !res.status().is_success() {
+        bail!("API Error: {}", res.status());
+    }
+    let scopes = res.headers().get("X-OAuth-Scopes")
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.split(',').map(str::trim).filter(|s| !s.is_empty()).map(String::from).collect());
+    let body = res.json().context("Failed to parse API response")?;
+    Ok(Some((body, scopes)))
+}
+
+#[cfg(test)]
+pub(crate) mod test_server {
+    use std::thread;
+
+    /// Response returned by the mock server: `(status, extra headers, JSON body)`. / モックサーバーが返す応答（ステータス・追加ヘッダー・JSON 本文）。
+    pub type Reply = (u16, Vec<(&'static str, &'static str)>, &'static str);
+
+    /// Request received by the mock server. / モックサーバーが受信したリクエスト。
+    #[derive(Debug)]
+    pub struct Received {
+        pub method: String,
+        pub path: String,
+        pub authorization: Option<String>,
+        pub body: String,
+    }
+
+    /// Start a server that answers the given `(status, headers, body)` responses in order, one per request.
+    /// 指定した `(ステータス, ヘッダー, 本文)` の応答を1リクエストずつ順に返すサーバーを起動します。
+    ///
+    /// Returns the base URL and a handle that yields the received requests.
+    /// ベース URL と、受信したリクエストを返すハンドルを返します。
+    pub fn start(responses: Vec<Reply>) -> (String, thread::JoinHandle<Vec<Received>>) {
+        let server = tiny_http::Server::http("127.0.0.1:0").unwrap();
+        let base = format!("http://{}", server.server_addr());
+        let handle = thread::spawn(move || {
+            let mut received = Vec::new();
+            for (status, headers, body) in responses {
+                let mut request = server.recv().unwrap();
+                let mut text = String::new();
+                request.as_reader().read_to_string(&mut text).unwrap();
+                received.push(Received {
+                    method: request.method().to_string(),
+                    path: request.url().to_string(),
+                    authorization: request.headers().iter()
+                        .find(|h| h.field.equiv("Authorization"))
+                        .map(|h| h.value.to_string()),
+                    body: text,
+                });
+                let mut response = tiny_http::Response::from_string(body).with_status_code(status)
+                    .with_header(tiny_http::Header::from_bytes("Content-Type", "application/json").unwrap());
+                for (name, value) in headers {
+                    response.add_header(tiny_http::Header::from_bytes(name, value).unwrap());
+                }
+                request.respond(response).unwrap();
+            }
+            received
+        });
+        (base, handle)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ProviderConfig;
+
+    #[test]
+    fn test_provider_for_host() {
+        let mut config = AppConfig::default();
+        assert_eq!(provider_for_host(&config, "github.com").unwrap().name(), "GitHub");
+        assert_eq!(provider_for_host(&config, "gitlab.com").unwrap().name(), "GitLab");
+        assert_eq!(provider_for_host(&config, "codeberg.org").unwrap().name(), "Gitea");
+        assert_eq!(provider_for_host(&config, "bitbucket.org").unwrap().name(), "Bitbucket");
+        // 不明なホストは GitHub Enterprise Server とみなし、クライアント ID が必要
+        assert!(provider_for_host(&config, "git.example.com").is_err());
+
+        config.providers.insert("git.example.com".into(), ProviderConfig { kind: Some(ProviderKind::Gitea), ..Default::default() });
+        assert_eq!(provider_for_host(&config, "git.example.com").unwrap().name(), "Gitea");
+    }
+
+    #[test]
+    fn test_scopes_default_preset_and_override() {
+        let mut config = AppConfig::default();
+        assert_eq!(provider_for_host(&config, "github.com").unwrap().scopes(), ["repo", "read:user"]);
+        assert_eq!(provider_for_host(&config, "gitlab.com").unwrap().scopes(), ["read_user", "write_repository"]);
+        assert!(provider_for_host(&config, "bitbucket.org").unwrap().scopes().is_empty());
+
+        let preset = vec!["repo".to_string(), "workflow".to_string()];
+        config.providers.insert("github.com".into(), ProviderConfig { scopes: Some(preset.clone()), ..Default::default() });
+        let github = provider_for_host(&config, "github.com").unwrap();
+        assert_eq!(github.scopes(), preset);
+        // GitHub の認可コードフローはクライアントシークレットが必要
+        assert!(github.authorization_code().is_none());
+
+        config.providers.get_mut("github.com").unwrap().client_secret = Some("secret".into());
+        let github = provider_for_host(&config, "github.com").unwrap();
+        assert_eq!(github.authorization_code().unwrap().scopes, "repo workflow");
+
+        let requested = vec!["read:user".to_string()];
+        assert_eq!(provider_with_scopes(&config, "github.com", Some(&requested)).unwrap().scopes(), requested);
+    }
+
+    #[test]
+    fn test_forgejo_alias() {
+        let settings: ProviderConfig = toml::from_str("kind = \"forgejo\"").unwrap();
+        assert_eq!(settings.kind, Some(ProviderKind::Gitea));
+    }
+
+    #[test]
+    fn test_refresh_token_reads_error_from_failed_status() {
+        let (base, handle) = test_server::start(vec![(400, vec![], r#"{"error":"invalid_grant","error_description":"expired"}"#)]);
+        let err = refresh_token(&format!("{}/oauth/token", base), "client", None, "old").unwrap_err().to_string();
+        assert!(err.contains("invalid_grant"), "{}", err);
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn test_fetch_authorized_reads_scopes_and_rejection() {
+        let (base, handle) = test_server::start(vec![
+            (200, vec![("X-OAuth-Scopes", "repo, read:user")], r#"{"login":"octocat"}"#),
+            (401, vec![], r#"{"message":"Bad credentials"}"#),
+            (403, vec![("X-RateLimit-Remaining", "4999")], r#"{"message":"Forbidden"}"#),
+            (403, vec![("X-RateLimit-Remaining", "0")], r#"{"message":"API rate limit exceeded"}"#),
+            (500, vec![], r#"{"message":"Server Error"}"#),
+        ]);
+        let client = Client::new();
+        let fetch = || fetch_authorized::<serde_json::Value>(client.get(format!("{}/user", base)));
+        let (body, scopes) = fetch().unwrap().unwrap();
+        assert_eq!(body["login"], "octocat");
+        assert_eq!(scopes.unwrap(), ["repo", "read:user"]);
+        assert!(fetch().unwrap().is_none());
+        assert!(fetch().unwrap().is_none());
+        // レート制限やサーバーエラーはトークンが無効である根拠にならない
+        assert!(fetch().unwrap_err().to_string().contains("rate limit"));
+        assert!(fetch().is_err());
+        handle.join().unwrap();
+    }
+}
@@ -0,0 +1,103 @@
+use super::{AccessToken, AuthProvider, DeviceAuthorization, Flow, Identity, TokenInfo};
+use crate::config::ProviderConfig;
+use anyhow::{Context, Result, bail};
+use reqwest::blocking::Client;
+use serde::Deserialize;
+
+/// [Production Use] Bitbucket Cloud provider. The user creates an app password and enters it with the username.
+/// 【本番用】Bitbucket Cloud のプロバイダー。ユーザーがアプリパスワードを作成し、ユーザー名とともに入力します。
+pub struct Bitbucket {
+    web_url: String,
+    api_url: String,
+}
+
+#[derive(Deserialize)]
+struct BitbucketUser {
+    username: String,
+    display_name: Option<String>,
+}
+
+impl From<BitbucketUser> for Identity {
+    fn from(user: BitbucketUser) -> Self {
+        Self { login: user.username, name: user.display_name, email: None }
+    }
+}
+
+impl Bitbucket {
+    /// Create a provider for `host`. URLs default to `https://<host>` and `https://api.bitbucket.org/2.0`.
+    /// `host` のプロバイダーを作成します。URL の既定値は `https://<host>` と `https://api.bitbucket.org/2.0` です。
+    pub fn new(host: &str, settings: &ProviderConfig) -> Self {
+        let web_url = super::trim_url(settings.web_url.as_deref().unwrap_or(&format!("https://{}", host)));
+        let api_url = super::trim_url(settings.api_url.as_deref().unwrap_or("https://api.bitbucket.org/2.0"));
+        Self { web_url, api_url }
+    }
+
+    fn settings_url(&self) -> String {
+        format!("{}/account/settings/app-passwords/", self.web_url)
+    }
+}
+
+impl AuthProvider for Bitbucket {
+    fn name(&self) -> &'static str {
+        "Bitbucket"
+    }
+
+    fn start(&self) -> Result<Flow> {
+        Ok(Flow::AppPassword { url: self.settings_url() })
+    }
+
+    fn poll(&self, _device: &DeviceAuthorization) -> Result<AccessToken> {
+        bail!("Bitbucket does not support the device flow")
+    }
+
+    fn identity(&self, login: Option<&str>, secret: &str) -> Result<Identity> {
+        self.validate(login, secret)?
+            .map(|info| info.identity)
+            .context("Bitbucket rejected the app password")
+    }
+
+    fn validate(&self, login: Option<&str>, secret: &str) -> Result<Option<TokenInfo>> {
+        let login = login.context("Bitbucket app passwords require the username")?;
+        let request = Client::new().get(format!("{}/user", self.api_url)).basic_auth(login, Some(secret));
+        Ok(super::fetch_authorized::<BitbucketUser>(request)?
+            .map(|(user, scopes)| TokenInfo { identity: user.into(), scopes }))
+    }
+
+    fn refresh(&self, _refresh_token: &str) -> Result<AccessToken> {
+        bail!("Bitbucket app passwords cannot be refreshed")
+    }
+
+    fn revoke(&self, _secret: &str) -> Result<()> {
+        bail!("Bitbucket app passwords cannot be revoked through the API: delete it at {}", self.settings_url())
+    }
+
+    fn can_revoke(&self) -> bool {
+        false
+    }
+
+    fn revocation_url(&self) -> String {
+        self.settings_url()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::test_server;
+
+    #[test]
+    fn test_validate_uses_basic_auth() {
+        let (base, handle) = test_server::start(vec![(200, vec![], r#"{"username":"bbuser","display_name":"BB User"}"#)]);
+        let bitbucket = Bitbucket::new("bitbucket.org", &ProviderConfig { api_url: Some(base), ..Default::default() });
+        assert_eq!(bitbucket.start().unwrap(), Flow::AppPassword { url: "https://bitbucket.org/account/settings/app-passwords/".into() });
+        assert!(bitbucket.validate(None, "secret").is_err());
+
+        let identity = bitbucket.identity(Some("bbuser"), "app-password").unwrap();
+        assert_eq!(identity.login, "bbuser");
+        assert_eq!(identity.name.as_deref(), Some("BB User"));
+        let received = handle.join().unwrap();
+        assert_eq!(received[0].path, "/user");
+        // "bbuser:app-password" の Base64
+        assert_eq!(received[0].authorization.as_deref(), Some("Basic YmJ1c2VyOmFwcC1wYXNzd29yZA=="));
+    }
+}
@@ -0,0 +1,143 @@
+use super::{AccessToken, AuthCodeClient, AuthProvider, DeviceAuthorization, Flow, Identity, TokenInfo};
+use crate::config::ProviderConfig;
+use anyhow::{Context, Result, bail};
+use reqwest::blocking::Client;
+use serde::Deserialize;
+
+/// Scopes requested by the browser sign-in by default / ブラウザでのサインインで既定で要求するスコープ
+const DEFAULT_SCOPES: [&str; 2] = ["read:user", "write:repository"];
+
+/// [Production Use] Gitea / Forgejo provider. The user creates an access token in the web UI and enters it.
+/// With an OAuth2 application (`client_id`), the browser sign-in with PKCE is also available.
+/// 【本番用】Gitea・Forgejo のプロバイダー。ユーザーが Web UI でアクセストークンを作成して入力します。
+/// OAuth2 アプリケーション（`client_id`）を設定した場合は、PKCE によるブラウザでのサインインも使用できます。
+pub struct Gitea {
+    web_url: String,
+    api_url: String,
+    client_id: Option<String>,
+    client_secret: Option<String>,
+    redirect_port: u16,
+    scopes: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct GiteaUser {
+    login: String,
+    full_name: Option<String>,
+    email: Option<String>,
+}
+
+impl From<GiteaUser> for Identity {
+    fn from(user: GiteaUser) -> Self {
+        let name = user.full_name.filter(|n| !n.is_empty());
+        Self { login: user.login, name, email: user.email.filter(|e| !e.is_empty()) }
+    }
+}
+
+impl Gitea {
+    /// Create a provider for `host`. URLs default to `https://<host>` and `<web_url>/api/v1`.
+    /// `host` のプロバイダーを作成します。URL の既定値は `https://<host>` と `<web_url>/api/v1` です。
+    pub fn new(host: &str, settings: &ProviderConfig) -> Self {
+        let web_url = super::trim_url(settings.web_url.as_deref().unwrap_or(&format!("https://{}", host)));
+        let api_url = super::trim_url(settings.api_url.as_deref().unwrap_or(&format!("{}/api/v1", web_url)));
+        Self {
+            web_url,
+            api_url,
+            client_id: settings.client_id.clone(),
+            client_secret: settings.client_secret.clone(),
+            redirect_port: settings.redirect_port.unwrap_or(0),
+            scopes: settings.scopes.clone().unwrap_or_else(|| DEFAULT_SCOPES.map(String::from).to_vec()),
+        }
+    }
+
+    fn settings_url(&self) -> String {
+        format!("{}/user/settings/applications", self.web_url)
+    }
+}
+
+impl AuthProvider for Gitea {
+    fn name(&self) -> &'static str {
+        "Gitea"
+    }
+
+    fn start(&self) -> Result<Flow> {
+        Ok(Flow::AppPassword { url: self.settings_url() })
+    }
+
+    fn authorization_code(&self) -> Option<AuthCodeClient> {
+        Some(AuthCodeClient {
+            authorize_url: format!("{}/login/oauth/authorize", self.web_url),
+            token_url: format!("{}/login/oauth/access_token", self.web_url),
+            client_id: self.client_id.clone()?,
+            client_secret: self.client_secret.clone(),
+            scopes: self.scopes.join(" "),
+            redirect_port: self.redirect_port,
+        })
+    }
+
+    fn scopes(&self) -> &[String] {
+        &self.scopes
+    }
+
+    fn poll(&self, _device: &DeviceAuthorization) -> Result<AccessToken> {
+        bail!("Gitea does not support the device flow")
+    }
+
+    fn identity(&self, login: Option<&str>, secret: &str) -> Result<Identity> {
+        self.validate(login, secret)?
+            .map(|info| info.identity)
+            .context("Gitea rejected the access token")
+    }
+
+    fn validate(&self, _login: Option<&str>, secret: &str) -> Result<Option<TokenInfo>> {
+        let request = Client::new().get(format!("{}/user", self.api_url))
+            .header("Authorization", format!("token {}", secret));
+        Ok(super::fetch_authorized::<GiteaUser>(request)?
+            .map(|(user, scopes)| TokenInfo { identity: user.into(), scopes }))
+    }
+
+    fn refresh(&self, refresh_token: &str) -> Result<AccessToken> {
+        // OAuth2 アプリケーションで発行したトークンのみ更新できる
+        let client_id = self.client_id.as_deref().context("Gitea access tokens cannot be refreshed")?;
+        super::refresh_token(&format!("{}/login/oauth/access_token", self.web_url), client_id, self.client_secret.as_deref(), refresh_token)
+    }
+
+    fn revoke(&self, _secret: &str) -> Result<()> {
+        bail!("Gitea access tokens cannot be revoked through the API with the token itself: delete it at {}", self.settings_url())
+    }
+
+    fn can_revoke(&self) -> bool {
+        false
+    }
+
+    fn revocation_url(&self) -> String {
+        self.settings_url()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::test_server;
+
+    #[test]
+    fn test_app_password_flow_and_validate() {
+        let (base, handle) = test_server::start(vec![
+            (200, vec![], r#"{"id":1,"login":"forgeuser","full_name":"","email":"forge@example.com"}"#),
+            (401, vec![], r#"{"message":"token is required"}"#),
+        ]);
+        let gitea = Gitea::new("codeberg.org", &ProviderConfig { web_url: Some(format!("{}/", base)), ..Default::default() });
+        assert_eq!(gitea.start().unwrap(), Flow::AppPassword { url: format!("{}/user/settings/applications", base) });
+        assert!(gitea.authorization_code().is_none());
+
+        let identity = gitea.identity(None, "gt_token").unwrap();
+        assert_eq!(identity, Identity { login: "forgeuser".into(), name: None, email: Some("forge@example.com".into()) });
+        assert!(gitea.validate(None, "bad").unwrap().is_none());
+        // トークン自身では失効できないため、削除する場所を案内する
+        assert!(gitea.revoke("gt_token").unwrap_err().to_string().contains("/user/settings/applications"));
+
+        let received = handle.join().unwrap();
+        assert_eq!(received[0].path, "/api/v1/user");
+        assert_eq!(received[0].authorization.as_deref(), Some("token gt_token"));
+    }
+}
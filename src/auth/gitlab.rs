@@ -0,0 +1,217 @@
+use super::{AccessToken, AuthCodeClient, AuthProvider, DeviceAuthorization, Flow, Identity, TokenInfo, USER_AGENT};
+use crate::config::ProviderConfig;
+use anyhow::{Context, Result, bail};
+use reqwest::blocking::Client;
+use serde::Deserialize;
+
+/// Scopes requested by default / 既定で要求するスコープ
+const DEFAULT_SCOPES: [&str; 2] = ["read_user", "write_repository"];
+
+/// [Production Use] GitLab (gitlab.com / self-managed) provider using the OAuth 2.0 device authorization grant.
+/// 【本番用】OAuth 2.0 デバイス認可グラントを使用する GitLab（gitlab.com・セルフマネージド）のプロバイダー。
+pub struct GitLab {
+    web_url: String,
+    api_url: String,
+    client_id: Option<String>,
+    client_secret: Option<String>,
+    redirect_port: u16,
+    scopes: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct GitLabUser {
+    username: String,
+    name: Option<String>,
+    commit_email: Option<String>,
+    email: Option<String>,
+}
+
+impl From<GitLabUser> for Identity {
+    fn from(user: GitLabUser) -> Self {
+        let email = user.commit_email.or(user.email).filter(|e| !e.is_empty());
+        Self { login: user.username, name: user.name, email }
+    }
+}
+
+#[derive(Deserialize)]
+struct TokenInfoResponse {
+    #[serde(default)]
+    scope: Vec<String>,
+}
+
+impl GitLab {
+    /// Create a provider for `host`. URLs default to `https://<host>` and `<web_url>/api/v4`.
+    /// `host` のプロバイダーを作成します。URL の既定値は `https://<host>` と `<web_url>/api/v4` です。
+    pub fn new(host: &str, settings: &ProviderConfig) -> Self {
+        let web_url = super::trim_url(settings.web_url.as_deref().unwrap_or(&format!("https://{}", host)));
+        let api_url = super::trim_url(settings.api_url.as_deref().unwrap_or(&format!("{}/api/v4", web_url)));
+        Self {
+            web_url,
+            api_url,
+            client_id: settings.client_id.clone(),
+            client_secret: settings.client_secret.clone(),
+            redirect_port: settings.redirect_port.unwrap_or(0),
+            scopes: settings.scopes.clone().unwrap_or_else(|| DEFAULT_SCOPES.map(String::from).to_vec()),
+        }
+    }
+
+    fn client_id(&self) -> Result<&str> {
+        self.client_id.as_deref().with_context(|| format!(
+            "No OAuth client ID is configured for {}: register an application with the device grant and set client_id in [providers] of config.toml",
+            self.web_url))
+    }
+
+    fn token_url(&self) -> String {
+        format!("{}/oauth/token", self.web_url)
+    }
+}
+
+impl AuthProvider for GitLab {
+    fn name(&self) -> &'static str {
+        "GitLab"
+    }
+
+    fn start(&self) -> Result<Flow> {
+        let res = Client::new().post(format!("{}/oauth/authorize_device", self.web_url))
+            .header("Accept", "application/json")
+            .header("User-Agent", USER_AGENT)
+            .form(&[("client_id", self.client_id()?), ("scope", &self.scopes.join(" "))])
+            .send()
+            .context("Failed to connect to GitLab")?;
+        if !res.status().is_success() {
+            let status = res.status();
+            let error_text = res.text().unwrap_or_default();
+            bail!("GitLab API Error: {} - {}", status, error_text);
+        }
+        let device: DeviceAuthorization = res.json().context("Failed to parse device authorization response")?;
+        Ok(Flow::Device(device))
+    }
+
+    fn authorization_code(&self) -> Option<AuthCodeClient> {
+        Some(AuthCodeClient {
+            authorize_url: format!("{}/oauth/authorize", self.web_url),
+            token_url: self.token_url(),
+            client_id: self.client_id.clone()?,
+            client_secret: self.client_secret.clone(),
+            scopes: self.scopes.join(" "),
+            redirect_port: self.redirect_port,
+        })
+    }
+
+    fn scopes(&self) -> &[String] {
+        &self.scopes
+    }
+
+    fn poll(&self, device: &DeviceAuthorization) -> Result<AccessToken> {
+        super::poll_device_token(&self.token_url(), self.client_id()?, device)
+    }
+
+    fn identity(&self, login: Option<&str>, secret: &str) -> Result<Identity> {
+        self.validate(login, secret)?
+            .map(|info| info.identity)
+            .context("GitLab rejected the token")
+    }
+
+    fn validate(&self, _login: Option<&str>, secret: &str) -> Result<Option<TokenInfo>> {
+        let client = Client::new();
+        let Some((user, _)) = super::fetch_authorized::<GitLabUser>(
+            client.get(format!("{}/user", self.api_url)).bearer_auth(secret))? else { return Ok(None) };
+        // OAuth トークンのスコープはトークン情報エンドポイントから取得する（PAT 等では取得できない）
+        let scopes = super::fetch_authorized::<TokenInfoResponse>(
+            client.get(format!("{}/oauth/token/info", self.web_url)).bearer_auth(secret))
+            .ok()
+            .flatten()
+            .map(|(info, _)| info.scope);
+        Ok(Some(TokenInfo { identity: user.into(), scopes }))
+    }
+
+    fn refresh(&self, refresh_token: &str) -> Result<AccessToken> {
+        super::refresh_token(&self.token_url(), self.client_id()?, self.client_secret.as_deref(), refresh_token)
+    }
+
+    fn revoke(&self, secret: &str) -> Result<()> {
+        let res = Client::new().post(format!("{}/oauth/revoke", self.web_url))
+            .header("User-Agent", USER_AGENT)
+            .form(&[("client_id", self.client_id()?), ("token", secret)])
+            .send()
+            .context("Failed to connect to GitLab")?;
+        if !res.status().is_success() {
+            let status = res.status();
+            let error_text = res.text().unwrap_or_default();
+            bail!("GitLab API Error: {} - {}", status, error_text);
+        }
+        Ok(())
+    }
+
+    fn can_revoke(&self) -> bool {
+        self.client_id.is_some()
+    }
+
+    fn revocation_url(&self) -> String {
+        format!("{}/-/user_settings/applications", self.web_url)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::test_server;
+
+    fn provider(base: &str) -> GitLab {
+        GitLab::new("gitlab.example.com", &ProviderConfig {
+            web_url: Some(base.to_string()),
+            client_id: Some("gl-client".into()),
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn test_device_grant() {
+        let (base, handle) = test_server::start(vec![
+            (200, vec![], r#"{"device_code":"dc","user_code":"ABCD1234","verification_uri":"https://gitlab.example.com/oauth/device","expires_in":300,"interval":0}"#),
+            (200, vec![], r#"{"access_token":"glo_new","token_type":"Bearer","expires_in":7200,"refresh_token":"glr_new"}"#),
+        ]);
+        let gitlab = provider(&base);
+        let Flow::Device(device) = gitlab.start().unwrap() else { panic!("expected device flow") };
+        assert_eq!(device.user_code, "ABCD1234");
+        let token = gitlab.poll(&device).unwrap();
+        assert_eq!(token.access_token, "glo_new");
+        assert_eq!(token.expires_in, Some(7200));
+        assert_eq!(token.refresh_token.as_deref(), Some("glr_new"));
+
+        let received = handle.join().unwrap();
+        assert_eq!(received[0].path, "/oauth/authorize_device");
+        assert!(received[0].body.contains("client_id=gl-client"));
+        assert_eq!(received[1].path, "/oauth/token");
+        assert!(received[1].body.contains("device_code=dc"));
+    }
+
+    #[test]
+    fn test_validate_reads_identity_and_scopes() {
+        let (base, handle) = test_server::start(vec![
+            (200, vec![], r#"{"id":1,"username":"gluser","name":"GL User","email":"gl@example.com","commit_email":"commit@example.com"}"#),
+            (200, vec![], r#"{"resource_owner_id":1,"scope":["read_user","write_repository"],"expires_in":7000}"#),
+        ]);
+        let info = provider(&base).validate(None, "glo_token").unwrap().unwrap();
+        assert_eq!(info.identity, Identity { login: "gluser".into(), name: Some("GL User".into()), email: Some("commit@example.com".into()) });
+        assert_eq!(info.scopes.unwrap(), ["read_user", "write_repository"]);
+        let received = handle.join().unwrap();
+        assert_eq!(received[0].path, "/api/v4/user");
+        assert_eq!(received[0].authorization.as_deref(), Some("Bearer glo_token"));
+    }
+
+    #[test]
+    fn test_requires_client_id() {
+        let gitlab = GitLab::new("gitlab.com", &ProviderConfig::default());
+        assert!(gitlab.start().unwrap_err().to_string().contains("client_id"));
+        assert!(gitlab.authorization_code().is_none());
+    }
+
+    #[test]
+    fn test_authorization_code_client() {
+        let client = provider("https://gitlab.example.com/").authorization_code().unwrap();
+        assert_eq!(client.authorize_url, "https://gitlab.example.com/oauth/authorize");
+        assert_eq!(client.token_url, "https://gitlab.example.com/oauth/token");
+        assert_eq!(client.client_id, "gl-client");
+    }
+}
@@ -0,0 +1,214 @@
+use super::AccessToken;
+use anyhow::{Context, Result, anyhow, bail};
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use chacha20poly1305::aead::OsRng;
+use chacha20poly1305::aead::rand_core::RngCore;
+use reqwest::Url;
+use reqwest::blocking::Client;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use tiny_http::{Header, Request, Response, Server};
+
+/// How long to wait for the browser to come back to the loopback listener / ブラウザがループバックのリスナーへ戻るまで待つ時間
+pub const TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Path of the redirect URI on the loopback listener / ループバックのリスナーのリダイレクト URI のパス
+const CALLBACK_PATH: &str = "/callback";
+
+/// OAuth client for the authorization code flow with PKCE (RFC 7636) and a loopback redirect (RFC 8252).
+/// PKCE（RFC 7636）とループバックリダイレクト（RFC 8252）を使用する認可コードフローの OAuth クライアント。
+#[derive(Debug, Clone, PartialEq)]
+pub struct AuthCodeClient {
+    /// Authorization endpoint / 認可エンドポイント
+    pub authorize_url: String,
+    /// Token endpoint / トークンエンドポイント
+    pub token_url: String,
+    /// Client ID / クライアント ID
+    pub client_id: String,
+    /// Client secret, for providers that require it even with PKCE / クライアントシークレット（PKCE でも必要なプロバイダー向け）
+    pub client_secret: Option<String>,
+    /// Space-separated scopes to request / 要求するスコープ（空白区切り）
+    pub scopes: String,
+    /// Port of the loopback listener (`0` picks a free port) / ループバックのリスナーのポート（`0` の場合は空いているポート）
+    pub redirect_port: u16,
+}
+
+/// Random URL-safe string of 32 bytes of entropy. / 32 バイトの乱数から作った URL で使用できる文字列。
+fn random_string() -> String {
+    let mut bytes = [0u8; 32];
+    OsRng.fill_bytes(&mut bytes);
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+/// S256 code challenge of a code verifier. / コード検証子の S256 コードチャレンジ。
+fn code_challenge(verifier: &str) -> String {
+    URL_SAFE_NO_PAD.encode(Sha256::digest(verifier.as_bytes()))
+}
+
+/// Sign in through the browser and return the issued token.
+/// ブラウザでサインインし、発行されたトークンを返します。
+///
+/// Starts a listener on `127.0.0.1`, passes the authorization URL to `open` (which should open it in the browser),
+/// waits for the redirect carrying the authorization code, and exchanges the code with the PKCE code verifier.
+/// `127.0.0.1` でリスナーを起動し、認可 URL を `open`（ブラウザで開く処理）に渡した後、認可コードを含むリダイレクトを待ち、
+/// PKCE のコード検証子とともに認可コードをトークンと交換します。
+///
+/// # Errors
+/// リスナーを起動できない場合、`TIMEOUT` 以内にリダイレクトが来ない場合、`state` が一致しない場合、
+/// ユーザーが拒否した場合、トークンの交換に失敗した場合にエラーを返します。
+pub fn authorize(client: &AuthCodeClient, open: impl FnOnce(&str)) -> Result<AccessToken> {
+    let server = Server::http(("127.0.0.1", client.redirect_port))
+        .map_err(|e| anyhow!("Failed to listen on 127.0.0.1:{}: {}", client.redirect_port, e))?;
+    let port = server.server_addr().to_ip().context("Loopback listener has no IP address")?.port();
+    let redirect_uri = format!("http://127.0.0.1:{}{}", port, CALLBACK_PATH);
+    let verifier = random_string();
+    let state = random_string();
+
+    let url = Url::parse_with_params(&client.authorize_url, &[
+        ("response_type", "code"),
+        ("client_id", client.client_id.as_str()),
+        ("redirect_uri", redirect_uri.as_str()),
+        ("scope", client.scopes.as_str()),
+        ("state", state.as_str()),
+        ("code_challenge", code_challenge(&verifier).as_str()),
+        ("code_challenge_method", "S256"),
+    ]).with_context(|| format!("Invalid authorization URL: {}", client.authorize_url))?;
+    open(url.as_str());
+
+    let code = wait_for_code(&server, &state, TIMEOUT)?;
+    let mut form = vec![
+        ("grant_type", "authorization_code"),
+        ("code", code.as_str()),
+        ("redirect_uri", redirect_uri.as_str()),
+        ("client_id", client.client_id.as_str()),
+        ("code_verifier", verifier.as_str()),
+    ];
+    if let Some(secret) = &client.client_secret {
+        form.push(("client_secret", secret));
+    }
+    let body = super::post_token_form(&Client::new(), &client.token_url, &form)?;
+    if let Some(err) = body.error {
+        bail!("Authorization error: {} {}", err, body.error_description.unwrap_or_default());
+    }
+    body.into_token().context("Token response did not contain an access token")
+}
+
+/// Wait for the redirect to the callback path and return the authorization code.
+/// コールバックのパスへのリダイレクトを待ち、認可コードを返します。
+///
+/// それ以外のパスへのリクエスト（`/favicon.ico` 等）には 404 を返して待機を続けます。
+/// `state` が一致しないリクエスト（ブラウザの先読みや以前のサインインのタブ等）にはエラーページを返し、
+/// `timeout` まで一致するリクエストを待ち続けます。
+fn wait_for_code(server: &Server, state: &str, timeout: Duration) -> Result<String> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        let Some(request) = server.recv_timeout(remaining)? else {
+            bail!("Timed out waiting for the browser to return to gas");
+        };
+        let url = Url::parse(&format!("http://127.0.0.1{}", request.url()))?;
+        if url.path() != CALLBACK_PATH {
+            let _ = request.respond(Response::empty(404));
+            continue;
+        }
+        let params: HashMap<String, String> = url.query_pairs().into_owned().collect();
+        if params.get("state").map(String::as_str) != Some(state) {
+            respond_page(request, "Sign-in failed: the authorization response did not match this sign-in (state mismatch)");
+            continue;
+        }
+        let result = if let Some(err) = params.get("error") {
+            Err(anyhow!("Authorization error: {} {}", err, params.get("error_description").map_or("", String::as_str)))
+        } else {
+            params.get("code").cloned().context("The authorization response did not contain a code")
+        };
+        match &result {
+            Ok(_) => respond_page(request, "Signed in. You can close this window and return to the terminal."),
+            Err(e) => respond_page(request, &format!("Sign-in failed: {}", e)),
+        }
+        return result;
+    }
+}
+
+/// ブラウザに結果のメッセージを表示する HTML ページを返します。
+fn respond_page(request: Request, message: &str) {
+    let html = format!("<!DOCTYPE html><html><body><p>gas: {}</p></body></html>", message.replace('<', "&lt;"));
+    let header = Header::from_bytes("Content-Type", "text/html; charset=utf-8").expect("valid header");
+    let _ = request.respond(Response::from_string(html).with_header(header));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::test_server;
+    use std::thread;
+
+    fn client(token_url: String) -> AuthCodeClient {
+        AuthCodeClient {
+            authorize_url: "https://git.example.com/oauth/authorize".into(),
+            token_url,
+            client_id: "test-client".into(),
+            client_secret: None,
+            scopes: "read_user write_repository".into(),
+            redirect_port: 0,
+        }
+    }
+
+    /// 認可 URL を受け取ったブラウザの代わりに、`state` を書き換えた上でリダイレクト URI へアクセスします。
+    fn browser(authorize_url: &str, state: Option<&str>) -> (HashMap<String, String>, thread::JoinHandle<String>) {
+        let params: HashMap<String, String> = Url::parse(authorize_url).unwrap().query_pairs().into_owned().collect();
+        let redirect = Url::parse_with_params(&params["redirect_uri"], &[
+            ("code", "auth-code"),
+            ("state", state.unwrap_or(&params["state"])),
+        ]).unwrap();
+        let handle = thread::spawn(move || reqwest::blocking::get(redirect).unwrap().text().unwrap());
+        (params, handle)
+    }
+
+    #[test]
+    fn test_authorize_exchanges_code_with_verifier() {
+        let (base, server) = test_server::start(vec![(200, vec![], r#"{"access_token":"gho_new","token_type":"bearer","scope":"repo"}"#)]);
+        let mut sent = None;
+        let token = authorize(&client(format!("{}/oauth/token", base)), |url| sent = Some(browser(url, None))).unwrap();
+        assert_eq!(token.access_token, "gho_new");
+
+        let (params, page) = sent.unwrap();
+        assert!(page.join().unwrap().contains("Signed in"));
+        assert_eq!(params["code_challenge_method"], "S256");
+        assert_eq!(params["scope"], "read_user write_repository");
+        assert!(params["redirect_uri"].starts_with("http://127.0.0.1:"));
+
+        let body = &server.join().unwrap()[0].body;
+        let form: HashMap<String, String> = Url::parse(&format!("http://localhost/?{}", body)).unwrap().query_pairs().into_owned().collect();
+        assert_eq!(form["grant_type"], "authorization_code");
+        assert_eq!(form["code"], "auth-code");
+        assert_eq!(form["redirect_uri"], params["redirect_uri"]);
+        assert_eq!(code_challenge(&form["code_verifier"]), params["code_challenge"]);
+    }
+
+    #[test]
+    fn test_wait_for_code_ignores_state_mismatch() {
+        let server = Server::http("127.0.0.1:0").unwrap();
+        let port = server.server_addr().to_ip().unwrap().port();
+        let callback = move |state: &str| {
+            let url = format!("http://127.0.0.1:{}{}?code=auth-code&state={}", port, CALLBACK_PATH, state);
+            reqwest::blocking::get(url).unwrap().text().unwrap()
+        };
+        let browser = thread::spawn(move || (callback("stale"), callback("expected")));
+
+        // 一致しない state のリクエストでサインインを中断せず、一致するリクエストを待ち続ける
+        assert_eq!(wait_for_code(&server, "expected", Duration::from_secs(10)).unwrap(), "auth-code");
+        let (stale, expected) = browser.join().unwrap();
+        assert!(stale.contains("state mismatch"), "{}", stale);
+        assert!(expected.contains("Signed in"), "{}", expected);
+
+        let err = wait_for_code(&server, "expected", Duration::from_millis(200)).unwrap_err();
+        assert!(err.to_string().contains("Timed out"), "{}", err);
+    }
+
+    #[test]
+    fn test_code_challenge_is_base64url_sha256() {
+        assert_eq!(code_challenge("dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFzXgmk"), "a3yPx_Nc25HlhmcIhVsYrS7ERBXeijOQCWY4dPIvExs");
+    }
+}
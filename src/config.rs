@@ -1,8 +1,9 @@
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::fs;
 use std::path::{Path, PathBuf};
 use anyhow::{Context, Result};
+use crate::credential::SecretKind;
 
 /// 対応している言語
 /// 対応している言語を表す列挙型
@@ -37,11 +38,315 @@ pub struct AppConfig {
     #[serde(default)]
     pub accounts: HashMap<String, AccountConfig>,
 
-    /// ディレクトリの絶対パスからアカウントのニックネームへのマップ
+    /// ディレクトリのパスまたはパターンからアカウントへのマップ
     /// 
     /// 特定のディレクトリ配下で Git コマンドを実行する際に、どのアカウントを使用するかを定義します。
+    /// キーには絶対パスのほか、グロブ（`~/src/work-*/**`）や `re:` で始まる正規表現を指定できます。
     #[serde(default)]
-    pub path_rules: HashMap<String, String>,
+    pub path_rules: HashMap<String, PathRule>,
+
+    /// リモートのホストと所有者（`github.com/acme-corp/*` 等）からアカウントのニックネームへのマップ
+    ///
+    /// 一致した場合は `path_rules` より優先されます。Git がリポジトリのパスを渡すよう、
+    /// 対象ホストで `credential.useHttpPath` を有効にする必要があります（`gas setup` で設定できます）。
+    #[serde(default)]
+    pub owner_rules: HashMap<String, String>,
+
+    /// Git から資格情報の拒否（`erase`）を通知された際の扱い
+    #[serde(default)]
+    pub erase_policy: ErasePolicy,
+
+    /// トークン等の秘密情報の保存先
+    #[serde(default)]
+    pub credential_backend: BackendKind,
+
+    /// `encrypted-file` バックエンドの設定
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub encrypted_file: Option<EncryptedFileConfig>,
+
+    /// `command` バックエンドの設定
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub command_backend: Option<CommandBackendConfig>,
+
+    /// `env` バックエンドの設定
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub env_backend: Option<EnvBackendConfig>,
+
+    /// ホスト名から認証プロバイダー（GitHub Enterprise Server 等）の設定へのマップ
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub providers: HashMap<String, ProviderConfig>,
+}
+
+/// ホストごとの認証プロバイダーの設定
+///
+/// 未指定の項目はホスト名から決まる既定値（github.com では GitHub の URL と gas 組み込みのクライアント ID）を使用します。
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+pub struct ProviderConfig {
+    /// プロバイダーの種類（未指定の場合はホスト名から判定し、不明なホストは GitHub Enterprise Server とみなす）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kind: Option<ProviderKind>,
+
+    /// Web の URL（例: `https://ghe.example.com`、未指定の場合は `https://<host>`）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub web_url: Option<String>,
+
+    /// API の URL（例: `https://ghe.example.com/api/v3`、未指定の場合は `<web_url>/api/v3`）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_url: Option<String>,
+
+    /// ブラウザ認証に使用する OAuth App のクライアント ID（github.com 以外の GitHub と GitLab では必須）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_id: Option<String>,
+
+    /// OAuth App のクライアントシークレット（GitHub でトークンを失効させる場合や、認可コードの交換に必要）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_secret: Option<String>,
+
+    /// 認可コードフローでリダイレクトを受け取るループバックのポート（未指定の場合は空いているポート）
+    ///
+    /// リダイレクト URI のポートまで一致させる必要があるプロバイダーで指定します。
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub redirect_port: Option<u16>,
+
+    /// ブラウザ認証で要求するスコープの既定値（未指定の場合はプロバイダーごとの組み込みの既定値）
+    ///
+    /// `gas add --scopes` を指定した場合はそちらが優先されます。
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scopes: Option<Vec<String>>,
+}
+
+/// 認証プロバイダー（Git ホスティングサービス）の種類
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ProviderKind {
+    /// GitHub / GitHub Enterprise Server（デバイスフロー）
+    GitHub,
+    /// GitLab（OAuth デバイスグラント）
+    GitLab,
+    /// Gitea / Forgejo（アクセストークン）
+    #[serde(alias = "forgejo")]
+    Gitea,
+    /// Bitbucket Cloud（アプリパスワード）
+    Bitbucket,
+}
+
+impl ProviderKind {
+    /// ホスト名から既定のプロバイダーを判定します。
+    pub fn detect(host: &str) -> Self {
+        match host {
+            "gitlab.com" => ProviderKind::GitLab,
+            "codeberg.org" | "gitea.com" => ProviderKind::Gitea,
+            "bitbucket.org" => ProviderKind::Bitbucket,
+            _ => ProviderKind::GitHub,
+        }
+    }
+}
+
+/// `env` バックエンドの設定
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+pub struct EnvBackendConfig {
+    /// 秘密情報を読み取る環境変数名のパターン（未指定の場合は `GAS_TOKEN_{NICKNAME}`）
+    ///
+    /// `{NICKNAME}` は大文字に変換し英数字以外を `_` にしたニックネーム、`{nickname}` はそのままのニックネームに置き換えられます。
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pattern: Option<String>,
+}
+
+/// `command` バックエンドの設定
+///
+/// 各コマンドはシェルを介さずに実行され、引数中の `{nickname}` と `{service}` が置き換えられます。
+/// `set` では秘密情報を標準入力で渡し、`get` では標準出力の1行目を秘密情報として扱います。
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+pub struct CommandBackendConfig {
+    /// 秘密情報を取得するコマンド（例: `pass show gas/{nickname}`）
+    pub get: String,
+
+    /// 秘密情報を保存するコマンド（例: `pass insert -m -f gas/{nickname}`）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub set: Option<String>,
+
+    /// 秘密情報を削除するコマンド（例: `pass rm -f gas/{nickname}`）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub delete: Option<String>,
+
+    /// 各コマンドのタイムアウト秒数（未指定の場合は 30 秒）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_secs: Option<u64>,
+}
+
+/// `encrypted-file` バックエンドの設定
+///
+/// `key_file` が指定されている場合はその内容から、指定されていない場合はパスフレーズから暗号鍵を導出します。
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+pub struct EncryptedFileConfig {
+    /// 暗号化ファイルのパス（未指定の場合は設定ディレクトリ内の `credentials.enc`）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<PathBuf>,
+
+    /// 暗号鍵の導出に使用する鍵ファイルのパス
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key_file: Option<PathBuf>,
+
+    /// パスフレーズを読み取る環境変数名（未指定の場合は `GAS_PASSPHRASE`）
+    ///
+    /// 環境変数が設定されていない場合は端末で入力を求めます。
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub passphrase_env: Option<String>,
+}
+
+/// 秘密情報の保存先（`CredentialStore` の実装）の種類
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum BackendKind {
+    /// OS の資格情報マネージャー (デフォルト)
+    #[default]
+    Keyring,
+    /// gas の設定ディレクトリ内の暗号化ファイル
+    EncryptedFile,
+    /// 外部コマンド（`pass` 等）
+    Command,
+    /// 環境変数（読み取り専用）
+    Env,
+}
+
+impl std::fmt::Display for BackendKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BackendKind::Keyring => write!(f, "keyring"),
+            BackendKind::EncryptedFile => write!(f, "encrypted-file"),
+            BackendKind::Command => write!(f, "command"),
+            BackendKind::Env => write!(f, "env"),
+        }
+    }
+}
+
+/// `path_rules` の値。アカウント名のみ、または優先度付きのテーブルとして記述できます。
+///
+/// ```toml
+/// [path_rules]
+/// "C:/projects/home" = "Home"
+/// "~/src/work-*/**" = { account = "Work", priority = 10 }
+/// ```
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum PathRule {
+    /// アカウントのニックネームのみ
+    Account(String),
+    /// アカウントのニックネームと優先度
+    Detailed {
+        /// 使用するアカウントのニックネーム
+        account: String,
+        /// 具体性が同じルールが複数一致した場合の優先度（大きいほど優先）
+        #[serde(default)]
+        priority: i32,
+    },
+}
+
+impl PathRule {
+    /// ルールが指すアカウントのニックネームを返します。
+    pub fn account(&self) -> &str {
+        match self {
+            PathRule::Account(account) | PathRule::Detailed { account, .. } => account,
+        }
+    }
+
+    /// ルールの優先度を返します（未指定の場合は 0）。
+    pub fn priority(&self) -> i32 {
+        match self {
+            PathRule::Account(_) => 0,
+            PathRule::Detailed { priority, .. } => *priority,
+        }
+    }
+}
+
+impl From<String> for PathRule {
+    fn from(account: String) -> Self {
+        PathRule::Account(account)
+    }
+}
+
+impl From<&str> for PathRule {
+    fn from(account: &str) -> Self {
+        PathRule::Account(account.to_string())
+    }
+}
+
+/// `git credential erase` を受け取った際の資格情報の扱いを表す列挙型
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum ErasePolicy {
+    /// 資格情報は残したまま無効とマークし、`store` で新しいトークンが保存されるまで使用しない (デフォルト)
+    #[default]
+    Invalidate,
+    /// 資格情報マネージャーから削除する
+    Delete,
+}
+
+/// コミット署名の形式
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum SigningFormat {
+    /// OpenPGP（GnuPG）の鍵で署名する
+    Gpg,
+    /// SSH の鍵で署名する
+    Ssh,
+    /// X.509 証明書（gpgsm）で署名する
+    X509,
+}
+
+impl SigningFormat {
+    /// `gpg.format` に設定する値を返します。
+    pub fn git_value(self) -> &'static str {
+        match self {
+            SigningFormat::Gpg => "openpgp",
+            SigningFormat::Ssh => "ssh",
+            SigningFormat::X509 => "x509",
+        }
+    }
+}
+
+impl std::fmt::Display for SigningFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SigningFormat::Gpg => write!(f, "gpg"),
+            SigningFormat::Ssh => write!(f, "ssh"),
+            SigningFormat::X509 => write!(f, "x509"),
+        }
+    }
+}
+
+/// 保存した秘密情報のメタデータ（秘密情報そのものは含みません）
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+pub struct SecretMeta {
+    /// 発行（保存）日時の UNIX 時間
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub issued_at: Option<u64>,
+
+    /// 有効期限の UNIX 時間（未指定の場合は期限なし）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<u64>,
+}
+
+/// アカウントのコミット署名の設定
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct SigningConfig {
+    /// 署名の形式（`gpg.format`）
+    pub format: SigningFormat,
+    /// 署名に使用する鍵（`user.signingkey`）
+    ///
+    /// `gpg` / `x509` では鍵 ID やフィンガープリント、`ssh` では鍵ファイルのパス（`~` を使用可）または `key::` で始まる公開鍵を指定します。
+    pub key: String,
+}
+
+impl SigningConfig {
+    /// `user.signingkey` に設定する値を返します。SSH の鍵ファイルのパスは `~` を展開します。
+    pub fn git_key(&self) -> String {
+        if self.format == SigningFormat::Ssh && !self.key.starts_with("key::") {
+            crate::path_match::expand_tilde(&self.key).to_string_lossy().replace('\\', "/")
+        } else {
+            self.key.clone()
+        }
+    }
 }
 
 /// 個別のアカウント情報を保持する構造体
@@ -49,14 +354,108 @@ pub struct AppConfig {
 pub struct AccountConfig {
     /// GitHub のユーザー名
     pub username: String,
+
+    /// 認証情報の提供を許可するホスト名の一覧
+    ///
+    /// `hosts` を持たない既存の設定ファイルは、読み込み時に `github.com` のみを許可する設定へ移行されます。
+    #[serde(default = "default_hosts")]
+    pub hosts: Vec<String>,
+
+    /// パッケージレジストリ用のトークン（`registry`）を提供するホスト名の一覧（例: `npm.pkg.github.com`）
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub registry_hosts: Vec<String>,
+
+    /// 認証情報の提供を許可するプロトコル（未指定の場合はすべて許可）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub protocol: Option<String>,
+
+    /// コミットに使用する名前（`user.name`）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+
+    /// コミットに使用するメールアドレス（`user.email`）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+
+    /// SSH で使用する秘密鍵のパス
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ssh_key: Option<PathBuf>,
+
+    /// コミット署名の設定（未指定の場合は署名の設定を行わない）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signing: Option<SigningConfig>,
+
+    /// 保存した秘密情報の種類ごとのメタデータ（発行日時・有効期限）
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub secrets: BTreeMap<SecretKind, SecretMeta>,
+
+    /// ブラウザ認証で OAuth トークンを取得した際に要求したスコープ（`gas verify` で付与されたスコープと比較する）
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub scopes: Vec<String>,
+
+    /// Git から拒否（`erase`）され、無効とマークされているかどうか
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub invalid: bool,
+}
+
+/// アカウントのニックネームをファイル名やホストエイリアスに使える形に変換します。
+///
+/// 英数字・`-`・`_` 以外の文字は `_` に置き換えます。
+pub fn safe_name(nickname: &str) -> String {
+    nickname.chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .collect()
+}
+
+/// ホスト指定のないアカウントに適用される既定のホスト
+pub const DEFAULT_HOST: &str = "github.com";
+
+fn default_hosts() -> Vec<String> {
+    vec![DEFAULT_HOST.to_string()]
+}
+
+impl AccountConfig {
+    /// `github.com` のみを許可するアカウント設定を作成します。
+    pub fn new(username: impl Into<String>) -> Self {
+        Self {
+            username: username.into(),
+            hosts: default_hosts(),
+            registry_hosts: Vec::new(),
+            protocol: None,
+            name: None,
+            email: None,
+            ssh_key: None,
+            signing: None,
+            secrets: BTreeMap::new(),
+            scopes: Vec::new(),
+            invalid: false,
+        }
+    }
+
+    /// 指定されたプロトコルとホストに対して、このアカウントの認証情報を提供してよいかを判定します。
+    ///
+    /// `hosts` と `registry_hosts` のいずれかに含まれるホストを許可します。
+    /// ホスト名は大文字・小文字を区別せずに比較します（ポート番号を含む場合はポートも一致する必要があります）。
+    pub fn allows(&self, protocol: &str, host: &str) -> bool {
+        if let Some(allowed) = &self.protocol
+            && !allowed.eq_ignore_ascii_case(protocol)
+        {
+            return false;
+        }
+        self.hosts.iter().chain(&self.registry_hosts).any(|h| h.eq_ignore_ascii_case(host))
+    }
+
+    /// 指定されたホストがパッケージレジストリとして登録されているかを判定します。
+    pub fn is_registry_host(&self, host: &str) -> bool {
+        self.registry_hosts.iter().any(|h| h.eq_ignore_ascii_case(host))
+    }
 }
 
 impl AppConfig {
-    /// 設定ファイルの保存先パスを取得します。
+    /// gas の設定ディレクトリを取得します（存在しない場合は作成します）。
     /// 
-    /// OS 標準の設定ディレクトリ（Windows の場合は AppData/Roaming など）内の
-    /// `gas/config.toml` を返します。
-    fn get_config_path() -> Result<PathBuf> {
+    /// OS 標準の設定ディレクトリ（Windows の場合は AppData/Roaming など）内の `gas` を返します。
+    pub fn config_dir() -> Result<PathBuf> {
         let config_dir = dirs::config_dir()
             .context("Could not determine config directory")?
             .join("gas");
@@ -65,7 +464,14 @@ impl AppConfig {
             fs::create_dir_all(&config_dir)?;
         }
         
-        Ok(config_dir.join("config.toml"))
+        Ok(config_dir)
+    }
+
+    /// 設定ファイルの保存先パスを取得します。
+    /// 
+    /// 設定ディレクトリ内の `gas/config.toml` を返します。
+    fn get_config_path() -> Result<PathBuf> {
+        Ok(Self::config_dir()?.join("config.toml"))
     }
 
     /// デフォルトのパスから設定を読み込みます。
@@ -115,10 +521,10 @@ impl AppConfig {
     /// # Errors
     /// シリアライズまたはファイルへの書き込みに失敗した場合にエラーを返します。
     pub fn save_to_path(&self, path: &Path) -> Result<()> {
-        if let Some(parent) = path.parent() {
-            if !parent.exists() {
-                fs::create_dir_all(parent)?;
-            }
+        if let Some(parent) = path.parent()
+            && !parent.exists()
+        {
+            fs::create_dir_all(parent)?;
         }
 
         let content = toml::to_string_pretty(self)
@@ -129,6 +535,12 @@ impl AppConfig {
             
         Ok(())
     }
+
+    /// `host` の認証プロバイダーの種類と設定を返します。
+    pub fn provider(&self, host: &str) -> (ProviderKind, ProviderConfig) {
+        let settings = self.providers.get(host).cloned().unwrap_or_default();
+        (settings.kind.unwrap_or_else(|| ProviderKind::detect(host)), settings)
+    }
 }
 
 #[cfg(test)]
@@ -141,9 +553,11 @@ mod tests {
         let dir = tempdir().unwrap();
         let file_path = dir.path().join("test_config.toml");
 
-        let mut config = AppConfig::default();
-        config.language = Some(Language::Ja);
-        config.default_account = Some("Work".to_string());
+        let config = AppConfig {
+            language: Some(Language::Ja),
+            default_account: Some("Work".to_string()),
+            ..Default::default()
+        };
 
         config.save_to_path(&file_path).expect("Failed to save");
         let loaded = AppConfig::load_from_path(&file_path).expect("Failed to load");
@@ -152,6 +566,21 @@ mod tests {
         assert_eq!(loaded.language, Some(Language::Ja));
     }
 
+    #[test]
+    fn test_secret_meta_round_trip() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("test_config.toml");
+        let mut account = AccountConfig::new("workuser");
+        account.secrets.insert(SecretKind::OAuth, SecretMeta { issued_at: Some(100), expires_at: Some(200) });
+        let mut config = AppConfig::default();
+        config.accounts.insert("Work".into(), account);
+
+        config.save_to_path(&file_path).unwrap();
+        let text = fs::read_to_string(&file_path).unwrap();
+        assert!(text.contains("[accounts.Work.secrets.oauth]"), "{}", text);
+        assert_eq!(AppConfig::load_from_path(&file_path).unwrap(), config);
+    }
+
     #[test]
     fn test_app_config_default_is_empty() {
         let config = AppConfig::default();
@@ -159,6 +588,7 @@ mod tests {
         assert!(config.default_account.is_none());
         assert!(config.accounts.is_empty());
         assert!(config.path_rules.is_empty());
+        assert!(config.owner_rules.is_empty());
     }
 
     #[test]
@@ -174,4 +604,45 @@ mod tests {
         assert_eq!(Language::En.to_string(), "English");
         assert_eq!(Language::Ja.to_string(), "日本語");
     }
+
+    #[test]
+    fn test_account_without_hosts_is_migrated_to_github() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("legacy.toml");
+        fs::write(&file_path, "[accounts.Work]\nusername = \"workuser\"\n").unwrap();
+
+        let loaded = AppConfig::load_from_path(&file_path).expect("Failed to load");
+        assert_eq!(loaded.accounts["Work"].hosts, vec!["github.com".to_string()]);
+        assert!(loaded.accounts["Work"].protocol.is_none());
+    }
+
+    #[test]
+    fn test_account_allows_host_and_protocol() {
+        let mut account = AccountConfig::new("user");
+        account.hosts.push("git.example.com:8443".into());
+        assert!(account.allows("https", "GitHub.com"));
+        assert!(account.allows("https", "git.example.com:8443"));
+        assert!(!account.allows("https", "git.example.com"));
+        assert!(!account.allows("https", "gitea.internal"));
+
+        account.protocol = Some("https".into());
+        assert!(account.allows("https", "github.com"));
+        assert!(!account.allows("http", "github.com"));
+    }
+
+    #[test]
+    fn test_path_rule_formats() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("rules.toml");
+        fs::write(&file_path, "[path_rules]\n\"/a\" = \"Home\"\n\"/b/*\" = { account = \"Work\", priority = 5 }\n").unwrap();
+
+        let loaded = AppConfig::load_from_path(&file_path).expect("Failed to load");
+        assert_eq!(loaded.path_rules["/a"], PathRule::Account("Home".into()));
+        assert_eq!(loaded.path_rules["/a"].priority(), 0);
+        assert_eq!(loaded.path_rules["/b/*"].account(), "Work");
+        assert_eq!(loaded.path_rules["/b/*"].priority(), 5);
+
+        loaded.save_to_path(&file_path).unwrap();
+        assert_eq!(AppConfig::load_from_path(&file_path).unwrap(), loaded);
+    }
 }
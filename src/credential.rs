@@ -1,22 +1,109 @@
+use crate::config::{AppConfig, BackendKind};
 use anyhow::{Context, Result};
 use keyring::Entry;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+pub mod command;
+pub mod encrypted_file;
+pub mod env;
+
+pub use command::CommandStore;
+pub use encrypted_file::EncryptedFileStore;
+pub use env::EnvStore;
 
 /// Service name used in Keyring / Keyringで使用するサービス名
 pub const SERVICE_NAME: &str = "gas";
 
+/// Kind of a secret stored for an account. / アカウントごとに保存する秘密情報の種類。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum SecretKind {
+    /// Personal access token or password / 個人用アクセストークンまたはパスワード
+    Pat,
+    /// OAuth access token / OAuth のアクセストークン
+    #[serde(rename = "oauth")]
+    #[value(name = "oauth")]
+    OAuth,
+    /// OAuth refresh token / OAuth のリフレッシュトークン
+    RefreshToken,
+    /// Token for a package registry (npm, container registry, ...) / パッケージレジストリ（npm、コンテナレジストリ等）用のトークン
+    Registry,
+}
+
+impl SecretKind {
+    /// All kinds / すべての種類
+    pub const ALL: [SecretKind; 4] = [SecretKind::Pat, SecretKind::OAuth, SecretKind::RefreshToken, SecretKind::Registry];
+
+    /// Name used in entry names and templates / エントリー名やテンプレートで使用する名前
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SecretKind::Pat => "pat",
+            SecretKind::OAuth => "oauth",
+            SecretKind::RefreshToken => "refresh-token",
+            SecretKind::Registry => "registry",
+        }
+    }
+
+    /// Name of the backend entry holding this kind of secret for `account`.
+    /// `account` のこの種類の秘密情報を保持するエントリー名を返します。
+    ///
+    /// PAT は従来どおりニックネームのみ、それ以外は `<nickname>:<kind>` となります。
+    pub fn entry_name(self, account: &str) -> String {
+        match self {
+            SecretKind::Pat => account.to_string(),
+            kind => format!("{}:{}", account, kind.as_str()),
+        }
+    }
+}
+
+impl fmt::Display for SecretKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// Error returned by `CredentialStore::get` when no secret is stored.
+/// Other errors (a locked keyring, a wrong passphrase, a failed command, ...) mean that the store could not be read.
+/// 秘密情報が保存されていない場合に `CredentialStore::get` が返すエラー。
+/// それ以外のエラー（Keyring のロック、パスフレーズの誤り、コマンドの失敗等）はストアを読み取れなかったことを表します。
+#[derive(Debug)]
+pub struct NotFound(pub String);
+
+impl fmt::Display for NotFound {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for NotFound {}
+
+/// Whether `err` means that no secret is stored. / `err` が秘密情報が保存されていないことを表すかどうか。
+pub fn is_not_found(err: &anyhow::Error) -> bool {
+    err.downcast_ref::<NotFound>().is_some()
+}
+
 /// An interface for reading and writing credential information.
 /// Used for abstraction with the OS credential manager (Keyring) and test mocks.
 /// 資格情報の読み書きを行うためのインターフェース。
 /// OS の資格情報マネージャー（Keyring）やテスト用の Mock との抽象化に使用されます。
+///
+/// Secrets are keyed by account nickname and kind. / 秘密情報はアカウントのニックネームと種類の組で管理されます。
 pub trait CredentialStore {
-    /// Save credentials. / 資格情報を保存します。
-    fn set(&self, service: &str, username: &str, password: &str) -> Result<()>;
-
-    /// Retrieve the saved credentials. / 保存されている資格情報を取得します。
-    fn get(&self, service: &str, username: &str) -> Result<String>;
-    
-    /// Delete the specified credentials. / 指定された資格情報を削除します。
-    fn delete(&self, service: &str, username: &str) -> Result<()>;
+    /// Save a secret. / 秘密情報を保存します。
+    fn set(&self, account: &str, kind: SecretKind, secret: &str) -> Result<()>;
+
+    /// Retrieve a saved secret. Returns a [`NotFound`] error if none is stored.
+    /// 保存されている秘密情報を取得します。保存されていない場合は [`NotFound`] エラーを返します。
+    fn get(&self, account: &str, kind: SecretKind) -> Result<String>;
+
+    /// Delete the specified secret. / 指定された秘密情報を削除します。
+    fn delete(&self, account: &str, kind: SecretKind) -> Result<()>;
+
+    /// Whether `set` and `delete` are refused by this store. / `set` と `delete` を受け付けないストアかどうか。
+    fn is_read_only(&self) -> bool {
+        false
+    }
 }
 
 /// [Production Use] Implementation using the OS Credential Manager (Credential Manager in Windows).
@@ -24,29 +111,73 @@ pub trait CredentialStore {
 pub struct KeyringStore;
 
 impl KeyringStore {
-    fn get_entry(service: &str, username: &str) -> Result<Entry> {
-        Entry::new(service, username).context("Failed to create keyring entry")
+    fn get_entry(account: &str, kind: SecretKind) -> Result<Entry> {
+        Entry::new(SERVICE_NAME, &kind.entry_name(account)).context("Failed to create keyring entry")
     }
 }
 
 impl CredentialStore for KeyringStore {
-    fn set(&self, service: &str, username: &str, password: &str) -> Result<()> {
-        let entry = Self::get_entry(service, username)?;
-        entry.set_password(password).context("Failed to save password to keyring")
+    fn set(&self, account: &str, kind: SecretKind, secret: &str) -> Result<()> {
+        let entry = Self::get_entry(account, kind)?;
+        entry.set_password(secret).context("Failed to save password to keyring")
     }
 
-    fn get(&self, service: &str, username: &str) -> Result<String> {
-        let entry = Self::get_entry(service, username)?;
-        entry.get_password().context("Failed to retrieve password from keyring")
+    fn get(&self, account: &str, kind: SecretKind) -> Result<String> {
+        let entry = Self::get_entry(account, kind)?;
+        match entry.get_password() {
+            Err(keyring::Error::NoEntry) => Err(NotFound("Password not found in keyring".into()).into()),
+            result => result.context("Failed to retrieve password from keyring"),
+        }
     }
 
-    fn delete(&self, service: &str, username: &str) -> Result<()> {
-        let entry = Self::get_entry(service, username)?;
+    fn delete(&self, account: &str, kind: SecretKind) -> Result<()> {
+        let entry = Self::get_entry(account, kind)?;
         let _ = entry.delete_password();
         Ok(())
     }
 }
 
+impl<T: CredentialStore + ?Sized> CredentialStore for Box<T> {
+    fn set(&self, account: &str, kind: SecretKind, secret: &str) -> Result<()> {
+        (**self).set(account, kind, secret)
+    }
+
+    fn get(&self, account: &str, kind: SecretKind) -> Result<String> {
+        (**self).get(account, kind)
+    }
+
+    fn delete(&self, account: &str, kind: SecretKind) -> Result<()> {
+        (**self).delete(account, kind)
+    }
+
+    fn is_read_only(&self) -> bool {
+        (**self).is_read_only()
+    }
+}
+
+/// Build the credential store of the given kind from the application settings.
+/// 指定した種類の資格情報ストアをアプリケーション設定から作成します。
+///
+/// # Errors
+/// バックエンドの設定が不足している場合や、利用できない場合にエラーを返します。
+pub fn build_store(kind: BackendKind, config: &AppConfig) -> Result<Box<dyn CredentialStore>> {
+    match kind {
+        BackendKind::Keyring => Ok(Box::new(KeyringStore)),
+        BackendKind::EncryptedFile => Ok(Box::new(EncryptedFileStore::from_config(config)?)),
+        BackendKind::Command => Ok(Box::new(CommandStore::from_config(config)?)),
+        BackendKind::Env => Ok(Box::new(EnvStore::from_config(config))),
+    }
+}
+
+/// Build the credential store selected by `credential_backend` in the settings.
+/// 設定の `credential_backend` で選択されている資格情報ストアを作成します。
+///
+/// # Errors
+/// バックエンドの作成に失敗した場合にエラーを返します。
+pub fn open_store(config: &AppConfig) -> Result<Box<dyn CredentialStore>> {
+    build_store(config.credential_backend, config)
+}
+
 // --- Test mock (publicly accessible for use in external tests) ---
 // --- テスト用モック（外部のテストからも使えるように公開）---
 #[cfg(test)]
@@ -68,29 +199,26 @@ mod mock {
                 storage: RefCell::new(HashMap::new()),
             }
         }
-        fn make_key(service: &str, username: &str) -> String {
-            format!("{}:{}", service, username)
-        }
     }
 
     impl CredentialStore for MockStore {
-        fn set(&self, service: &str, username: &str, password: &str) -> Result<()> {
-            let key = Self::make_key(service, username);
-            self.storage.borrow_mut().insert(key, password.to_string());
+        fn set(&self, account: &str, kind: SecretKind, secret: &str) -> Result<()> {
+            let key = kind.entry_name(account);
+            self.storage.borrow_mut().insert(key, secret.to_string());
             Ok(())
         }
 
-        fn get(&self, service: &str, username: &str) -> Result<String> {
-            let key = Self::make_key(service, username);
+        fn get(&self, account: &str, kind: SecretKind) -> Result<String> {
+            let key = kind.entry_name(account);
             self.storage
                 .borrow()
                 .get(&key)
                 .cloned()
-                .context("Password not found")
+                .ok_or_else(|| NotFound("Password not found".into()).into())
         }
 
-        fn delete(&self, service: &str, username: &str) -> Result<()> {
-            let key = Self::make_key(service, username);
+        fn delete(&self, account: &str, kind: SecretKind) -> Result<()> {
+            let key = kind.entry_name(account);
             self.storage.borrow_mut().remove(&key);
             Ok(())
         }
@@ -105,7 +233,53 @@ mod tests {
     #[test]
     fn test_set_and_get_password() {
         let store = MockStore::new();
-        store.set("test", "user", "pass").unwrap();
-        assert_eq!(store.get("test", "user").unwrap(), "pass");
+        store.set("user", SecretKind::Pat, "pass").unwrap();
+        assert_eq!(store.get("user", SecretKind::Pat).unwrap(), "pass");
+    }
+
+    #[test]
+    fn test_secret_kinds_are_separate() {
+        let store = MockStore::new();
+        store.set("user", SecretKind::Pat, "pat").unwrap();
+        store.set("user", SecretKind::OAuth, "oauth").unwrap();
+        assert_eq!(store.get("user", SecretKind::Pat).unwrap(), "pat");
+        assert_eq!(store.get("user", SecretKind::OAuth).unwrap(), "oauth");
+        assert!(is_not_found(&store.get("user", SecretKind::Registry).unwrap_err()));
+    }
+
+    #[test]
+    fn test_entry_name() {
+        // PAT は従来のエントリー名（ニックネームのみ）を使用する
+        assert_eq!(SecretKind::Pat.entry_name("Work"), "Work");
+        assert_eq!(SecretKind::OAuth.entry_name("Work"), "Work:oauth");
+        assert_eq!(SecretKind::RefreshToken.entry_name("Work"), "Work:refresh-token");
+    }
+
+    #[test]
+    fn test_boxed_store_delegates() {
+        let store: Box<dyn CredentialStore> = Box::new(MockStore::new());
+        store.set("user", SecretKind::Pat, "pass").unwrap();
+        assert_eq!(store.get("user", SecretKind::Pat).unwrap(), "pass");
+        store.delete("user", SecretKind::Pat).unwrap();
+        assert!(store.get("user", SecretKind::Pat).is_err());
+    }
+
+    #[test]
+    fn test_open_store_uses_configured_backend() {
+        use crate::config::{CommandBackendConfig, EnvBackendConfig};
+
+        // env バックエンド: パターンをニックネームそのものにし、常に設定されている PATH を読み取る
+        let mut config = AppConfig { credential_backend: BackendKind::Env, ..Default::default() };
+        config.env_backend = Some(EnvBackendConfig { pattern: Some("{nickname}".into()) });
+        let store = open_store(&config).unwrap();
+        assert!(store.is_read_only());
+        assert_eq!(store.get("PATH", SecretKind::Pat).unwrap(), std::env::var("PATH").unwrap());
+
+        // command バックエンド: `get` コマンドの出力を秘密情報として返す
+        if cfg!(unix) {
+            config.credential_backend = BackendKind::Command;
+            config.command_backend = Some(CommandBackendConfig { get: "echo token-{nickname}".into(), ..Default::default() });
+            assert_eq!(open_store(&config).unwrap().get("Work", SecretKind::Pat).unwrap(), "token-Work");
+        }
     }
 }
\ No newline at end of file
@@ -0,0 +1,222 @@
+use super::{CredentialStore, NotFound, SERVICE_NAME, SecretKind};
+use crate::config::{AppConfig, CommandBackendConfig};
+use anyhow::{Context, Result, bail};
+use std::io::{Read, Write};
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::Duration;
+use wait_timeout::ChildExt;
+use zeroize::Zeroizing;
+
+/// Default timeout of each command / 各コマンドの既定のタイムアウト
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// [Production Use] Delegates secrets to external commands such as `pass` or a password manager CLI.
+/// 【本番用】`pass` やパスワードマネージャーの CLI 等、外部コマンドに秘密情報の管理を委ねる実装。
+pub struct CommandStore {
+    settings: CommandBackendConfig,
+    timeout: Duration,
+}
+
+impl CommandStore {
+    /// Create a store from the command templates.
+    /// コマンドのテンプレートからストアを作成します。
+    pub fn new(settings: CommandBackendConfig) -> Self {
+        let timeout = settings.timeout_secs.map(Duration::from_secs).unwrap_or(DEFAULT_TIMEOUT);
+        Self { settings, timeout }
+    }
+
+    /// Build the store from the `[command_backend]` section of the settings.
+    /// 設定の `[command_backend]` セクションからストアを作成します。
+    ///
+    /// # Errors
+    /// `[command_backend]` が設定されていない場合にエラーを返します。
+    pub fn from_config(config: &AppConfig) -> Result<Self> {
+        let settings = config.command_backend.clone()
+            .context("credential_backend = \"command\" requires a [command_backend] section in config.toml")?;
+        Ok(Self::new(settings))
+    }
+
+    /// Split a template into arguments and fill in the placeholders.
+    /// テンプレートを引数に分割し、プレースホルダーを置き換えます。
+    ///
+    /// `{nickname}` は PAT ではニックネーム、それ以外の種類では `<nickname>:<kind>` に、`{kind}` は種類の名前に置き換えられます。
+    /// 置き換えは分割後の各引数に対して行うため、ニックネームに空白等が含まれていても引数は分割されません。
+    fn expand(template: &str, account: &str, kind: SecretKind) -> Result<Vec<String>> {
+        let args = shlex::split(template)
+            .with_context(|| format!("Invalid command template: {}", template))?;
+        if args.is_empty() {
+            bail!("Command template is empty");
+        }
+        Ok(args.into_iter()
+            .map(|arg| arg
+                .replace("{nickname}", &kind.entry_name(account))
+                .replace("{kind}", kind.as_str())
+                .replace("{service}", SERVICE_NAME))
+            .collect())
+    }
+
+    /// Run a command with an optional stdin payload and return its stdout.
+    /// コマンドを実行し（必要に応じて標準入力へ書き込み）、標準出力を返します。
+    ///
+    /// # Errors
+    /// コマンドの起動に失敗した場合、タイムアウトした場合、終了コードが 0 以外の場合にエラーを返します。
+    /// 終了コードが 0 以外の場合は標準エラー出力をエラーメッセージに含めます。
+    fn run(&self, args: &[String], input: Option<&str>) -> Result<Zeroizing<String>> {
+        let display = args.join(" ");
+        let mut child = Command::new(&args[0])
+            .args(&args[1..])
+            .stdin(if input.is_some() { Stdio::piped() } else { Stdio::null() })
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .with_context(|| format!("Failed to execute `{}`", display))?;
+
+        // パイプが詰まらないよう、終了を待つ間も別スレッドで読み続ける
+        let mut stdout = child.stdout.take().context("Failed to open stdout")?;
+        let mut stderr = child.stderr.take().context("Failed to open stderr")?;
+        let out_reader = thread::spawn(move || {
+            let mut buf = Zeroizing::new(Vec::new());
+            let _ = stdout.read_to_end(&mut buf);
+            buf
+        });
+        let err_reader = thread::spawn(move || {
+            let mut buf = Vec::new();
+            let _ = stderr.read_to_end(&mut buf);
+            buf
+        });
+        if let Some(input) = input {
+            // 入力を読まずに終了したコマンドへの書き込みエラーは無視し、終了コードで判定する
+            let mut stdin = child.stdin.take().context("Failed to open stdin")?;
+            let _ = stdin.write_all(input.as_bytes()).and_then(|_| stdin.write_all(b"\n"));
+        }
+
+        let Some(status) = child.wait_timeout(self.timeout)? else {
+            let _ = child.kill();
+            let _ = child.wait();
+            bail!("`{}` timed out after {} seconds", display, self.timeout.as_secs());
+        };
+        let out = out_reader.join().unwrap_or_default();
+        let err = err_reader.join().unwrap_or_default();
+        if !status.success() {
+            let stderr = String::from_utf8_lossy(&err);
+            bail!("`{}` failed ({}): {}", display, status, stderr.trim());
+        }
+        Ok(Zeroizing::new(String::from_utf8_lossy(&out).into_owned()))
+    }
+
+    fn template<'a>(&self, template: &'a Option<String>, op: &str) -> Result<&'a str> {
+        template.as_deref().with_context(|| format!("No `{}` command configured in [command_backend]", op))
+    }
+}
+
+impl CredentialStore for CommandStore {
+    fn set(&self, account: &str, kind: SecretKind, secret: &str) -> Result<()> {
+        let template = self.template(&self.settings.set, "set")?;
+        self.run(&Self::expand(template, account, kind)?, Some(secret))?;
+        Ok(())
+    }
+
+    fn get(&self, account: &str, kind: SecretKind) -> Result<String> {
+        let output = self.run(&Self::expand(&self.settings.get, account, kind)?, None)?;
+        let secret = output.lines().next().unwrap_or("").trim_end();
+        // 正常終了して何も出力しなかった場合のみ「保存されていない」とみなす
+        if secret.is_empty() {
+            return Err(NotFound(format!("`{}` returned no secret", self.settings.get)).into());
+        }
+        Ok(secret.to_string())
+    }
+
+    fn delete(&self, account: &str, kind: SecretKind) -> Result<()> {
+        let template = self.template(&self.settings.delete, "delete")?;
+        self.run(&Self::expand(template, account, kind)?, None)?;
+        Ok(())
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::os::unix::fs::PermissionsExt;
+    use std::path::Path;
+
+    /// 秘密情報をディレクトリ内のファイルとして扱うスタブスクリプトを作成します。
+    fn stub_script(dir: &Path) -> String {
+        let script = dir.join("stub.sh");
+        let body = format!(r#"#!/bin/sh
+store="{}"
+case "$1" in
+  get) if [ -f "$store/$2" ]; then cat "$store/$2"; else echo "no entry for $2" >&2; exit 3; fi ;;
+  set) cat > "$store/$2" ;;
+  delete) rm -f "$store/$2" ;;
+  hang) sleep 5 ;;
+esac
+"#, dir.display());
+        fs::write(&script, body).unwrap();
+        fs::set_permissions(&script, fs::Permissions::from_mode(0o755)).unwrap();
+        script.display().to_string()
+    }
+
+    fn stub_store(dir: &Path) -> CommandStore {
+        let script = stub_script(dir);
+        CommandStore::new(CommandBackendConfig {
+            get: format!("{} get {{service}}-{{nickname}}", script),
+            set: Some(format!("{} set {{service}}-{{nickname}}", script)),
+            delete: Some(format!("{} delete {{service}}-{{nickname}}", script)),
+            timeout_secs: Some(2),
+        })
+    }
+
+    #[test]
+    fn test_set_get_delete_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = stub_store(dir.path());
+        store.set("Work", SecretKind::Pat, "token123").unwrap();
+        assert_eq!(fs::read_to_string(dir.path().join("gas-Work")).unwrap(), "token123\n");
+        assert_eq!(store.get("Work", SecretKind::Pat).unwrap(), "token123");
+        store.set("Work", SecretKind::OAuth, "oauth-token").unwrap();
+        assert_eq!(fs::read_to_string(dir.path().join("gas-Work:oauth")).unwrap(), "oauth-token\n");
+        store.delete("Work", SecretKind::Pat).unwrap();
+        assert!(store.get("Work", SecretKind::Pat).is_err());
+    }
+
+    #[test]
+    fn test_nickname_is_a_single_argument() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = stub_store(dir.path());
+        store.set("My Work; rm -rf", SecretKind::Pat, "token").unwrap();
+        assert!(dir.path().join("gas-My Work; rm -rf").exists());
+    }
+
+    #[test]
+    fn test_error_includes_stderr() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = stub_store(dir.path());
+        let err = store.get("Missing", SecretKind::Pat).unwrap_err();
+        assert!(err.to_string().contains("no entry for gas-Missing"), "{}", err);
+        // 失敗したコマンドは「保存されていない」とはみなさない
+        assert!(!crate::credential::is_not_found(&err));
+    }
+
+    #[test]
+    fn test_timeout() {
+        let dir = tempfile::tempdir().unwrap();
+        let script = stub_script(dir.path());
+        let store = CommandStore::new(CommandBackendConfig {
+            get: format!("{} hang", script),
+            timeout_secs: Some(1),
+            ..Default::default()
+        });
+        let err = store.get("Work", SecretKind::Pat).unwrap_err().to_string();
+        assert!(err.contains("timed out"), "{}", err);
+    }
+
+    #[test]
+    fn test_missing_template() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = CommandStore::new(CommandBackendConfig { get: stub_script(dir.path()), ..Default::default() });
+        let err = store.delete("Work", SecretKind::Pat).unwrap_err().to_string();
+        assert!(err.contains("No `delete` command"));
+    }
+}
@@ -0,0 +1,427 @@
+use super::{CredentialStore, NotFound, SERVICE_NAME, SecretKind};
+use crate::config::{AppConfig, EncryptedFileConfig};
+use anyhow::{Context, Result, anyhow, bail};
+use argon2::{Algorithm, Argon2, Params, Version};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use zeroize::Zeroizing;
+
+/// Default file name of the encrypted credential file / 暗号化ファイルの既定のファイル名
+pub const DEFAULT_FILE_NAME: &str = "credentials.enc";
+
+/// Default environment variable holding the passphrase / パスフレーズを読み取る既定の環境変数名
+pub const DEFAULT_PASSPHRASE_ENV: &str = "GAS_PASSPHRASE";
+
+/// Current format version of the encrypted file / 暗号化ファイルの形式のバージョン
+const FORMAT_VERSION: u32 = 1;
+
+/// Length of the random salt given to the KDF / KDF に渡すソルトの長さ
+const SALT_LEN: usize = 16;
+
+/// Where the key material for the encryption key comes from.
+/// 暗号鍵の導出元。
+pub enum KeySource {
+    /// A passphrase / パスフレーズ
+    Passphrase(Zeroizing<String>),
+    /// The contents of a key file / 鍵ファイルの内容
+    KeyFile(PathBuf),
+    /// A passphrase asked on the terminal when first needed / 最初に必要になった時点で端末から入力するパスフレーズ
+    Prompt,
+}
+
+/// On-disk representation of the encrypted file.
+/// 暗号化ファイルの保存形式。
+#[derive(Serialize, Deserialize)]
+struct Envelope {
+    version: u32,
+    kdf: String,
+    m_cost: u32,
+    t_cost: u32,
+    p_cost: u32,
+    salt: String,
+    nonce: String,
+    ciphertext: String,
+}
+
+/// Key derived with Argon2 together with the salt and parameters it was derived for.
+/// Argon2 で導出した鍵と、導出に使用したソルト・パラメーター。
+struct DerivedKey {
+    salt: Vec<u8>,
+    params: Params,
+    key: Zeroizing<[u8; 32]>,
+}
+
+/// [Production Use] Stores all secrets in a single file encrypted with XChaCha20-Poly1305,
+/// using a key derived with Argon2id from a passphrase or key file.
+/// 【本番用】すべての秘密情報を1つのファイルに保存する実装。
+/// 暗号鍵はパスフレーズまたは鍵ファイルから Argon2id で導出し、XChaCha20-Poly1305 で暗号化します。
+pub struct EncryptedFileStore {
+    path: PathBuf,
+    source: KeySource,
+    params: Params,
+    /// Key material resolved from `source` / `source` から取得した鍵の元データ
+    material: RefCell<Option<Zeroizing<Vec<u8>>>>,
+    /// Last derived key, so Argon2 is not re-run for the same file / 最後に導出した鍵（同じファイルに対して Argon2 を再実行しないため）
+    key: RefCell<Option<DerivedKey>>,
+}
+
+impl EncryptedFileStore {
+    /// Create a store for the file at `path` with the default Argon2 parameters.
+    /// 既定の Argon2 のパラメーターで `path` のファイルを扱うストアを作成します。
+    pub fn new(path: PathBuf, source: KeySource) -> Self {
+        Self::with_params(path, source, Params::default())
+    }
+
+    /// Create a store with explicit Argon2 parameters (used for new writes only).
+    /// Argon2 のパラメーターを指定してストアを作成します（新たに書き込む際にのみ使用されます）。
+    pub fn with_params(path: PathBuf, source: KeySource, params: Params) -> Self {
+        Self { path, source, params, material: RefCell::new(None), key: RefCell::new(None) }
+    }
+
+    /// Build the store from the `[encrypted_file]` section of the settings.
+    /// 設定の `[encrypted_file]` セクションからストアを作成します。
+    ///
+    /// # Errors
+    /// 設定ディレクトリを特定できない場合にエラーを返します。
+    pub fn from_config(config: &AppConfig) -> Result<Self> {
+        let settings = config.encrypted_file.clone().unwrap_or_default();
+        let path = match &settings.path {
+            Some(path) => path.clone(),
+            None => AppConfig::config_dir()?.join(DEFAULT_FILE_NAME),
+        };
+        Ok(Self::new(path, Self::key_source(&settings)))
+    }
+
+    /// Decide the key source from the settings: key file, then passphrase env var, then prompt.
+    /// 設定から鍵の導出元を決定します（鍵ファイル、環境変数のパスフレーズ、端末入力の順）。
+    fn key_source(settings: &EncryptedFileConfig) -> KeySource {
+        if let Some(key_file) = &settings.key_file {
+            return KeySource::KeyFile(key_file.clone());
+        }
+        let env = settings.passphrase_env.as_deref().unwrap_or(DEFAULT_PASSPHRASE_ENV);
+        match std::env::var(env) {
+            Ok(passphrase) if !passphrase.is_empty() => KeySource::Passphrase(Zeroizing::new(passphrase)),
+            _ => KeySource::Prompt,
+        }
+    }
+
+    /// Path of the encrypted file / 暗号化ファイルのパス
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn resolve_material(source: &KeySource, prompt: &str, creating: bool) -> Result<Zeroizing<Vec<u8>>> {
+        match source {
+            KeySource::Passphrase(p) => Ok(Zeroizing::new(p.as_bytes().to_vec())),
+            KeySource::KeyFile(path) => {
+                let bytes = Zeroizing::new(fs::read(path)
+                    .with_context(|| format!("Failed to read key file: {}", path.display()))?);
+                if bytes.is_empty() { bail!("Key file is empty: {}", path.display()); }
+                Ok(bytes)
+            }
+            KeySource::Prompt => {
+                let mut prompt = dialoguer::Password::new().with_prompt(prompt);
+                if creating {
+                    prompt = prompt.with_confirmation("Confirm passphrase", "Passphrases do not match.");
+                }
+                let passphrase = Zeroizing::new(prompt.interact()?);
+                Ok(Zeroizing::new(passphrase.as_bytes().to_vec()))
+            }
+        }
+    }
+
+    fn material(&self) -> Result<Zeroizing<Vec<u8>>> {
+        if let Some(material) = self.material.borrow().as_ref() {
+            return Ok(material.clone());
+        }
+        let material = Self::resolve_material(&self.source, "Passphrase for the gas credential file", !self.path.exists())?;
+        *self.material.borrow_mut() = Some(material.clone());
+        Ok(material)
+    }
+
+    fn derive_key(material: &[u8], salt: &[u8], params: Params) -> Result<Zeroizing<[u8; 32]>> {
+        let mut key = Zeroizing::new([0u8; 32]);
+        Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+            .hash_password_into(material, salt, key.as_mut())
+            .map_err(|e| anyhow!("Failed to derive encryption key: {}", e))?;
+        Ok(key)
+    }
+
+    /// Key for `salt` and `params`, derived from the cached key material unless it was already derived.
+    /// `salt` と `params` に対応する鍵を返します。導出済みでなければキャッシュした鍵の元データから導出します。
+    fn key_for(&self, salt: &[u8], params: &Params) -> Result<Zeroizing<[u8; 32]>> {
+        if let Some(cached) = self.key.borrow().as_ref()
+            && cached.salt == salt
+            && cached.params == *params
+        {
+            return Ok(cached.key.clone());
+        }
+        let key = Self::derive_key(&self.material()?, salt, params.clone())?;
+        *self.key.borrow_mut() = Some(DerivedKey { salt: salt.to_vec(), params: params.clone(), key: key.clone() });
+        Ok(key)
+    }
+
+    /// Take an exclusive lock on `<file>.lock` for a whole load-modify-save cycle; it is released when the returned file is dropped.
+    /// 読み込みから保存までの間、`<ファイル名>.lock` の排他ロックを取得します。返されたファイルを破棄すると解放されます。
+    ///
+    /// Git は資格情報ヘルパーを並行して起動することがあるため（並列 fetch やサブモジュール）、
+    /// ロックがないと一方の更新がもう一方のリネームで失われます。
+    fn lock(&self) -> Result<fs::File> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut lock_name = self.path.file_name().context("Invalid credential file path")?.to_os_string();
+        lock_name.push(".lock");
+        let lock_path = self.path.with_file_name(lock_name);
+        let file = fs::OpenOptions::new().create(true).truncate(false).write(true).open(&lock_path)
+            .with_context(|| format!("Failed to open {}", lock_path.display()))?;
+        file.lock().with_context(|| format!("Failed to lock {}", lock_path.display()))?;
+        Ok(file)
+    }
+
+    /// Read and decrypt all entries. A missing file is an empty store.
+    /// すべてのエントリーを読み込んで復号します。ファイルが存在しない場合は空として扱います。
+    fn load(&self) -> Result<BTreeMap<String, String>> {
+        if !self.path.exists() {
+            return Ok(BTreeMap::new());
+        }
+        let text = fs::read_to_string(&self.path)
+            .with_context(|| format!("Failed to read credential file: {}", self.path.display()))?;
+        let envelope: Envelope = serde_json::from_str(&text).context("Credential file is corrupted")?;
+        if envelope.version != FORMAT_VERSION || envelope.kdf != "argon2id" {
+            bail!("Unsupported credential file format (version {}, kdf {})", envelope.version, envelope.kdf);
+        }
+        let params = Params::new(envelope.m_cost, envelope.t_cost, envelope.p_cost, Some(32))
+            .map_err(|e| anyhow!("Invalid KDF parameters in credential file: {}", e))?;
+        let salt = BASE64.decode(&envelope.salt).context("Credential file is corrupted")?;
+        let nonce = BASE64.decode(&envelope.nonce).context("Credential file is corrupted")?;
+        let ciphertext = BASE64.decode(&envelope.ciphertext).context("Credential file is corrupted")?;
+        if nonce.len() != 24 { bail!("Credential file is corrupted"); }
+
+        let key = self.key_for(&salt, &params)?;
+        let cipher = XChaCha20Poly1305::new(key.as_ref().into());
+        let plaintext = Zeroizing::new(cipher
+            .decrypt(XNonce::from_slice(&nonce), ciphertext.as_slice())
+            .map_err(|_| anyhow!("Failed to decrypt credential file (wrong passphrase or key file?)"))?);
+        serde_json::from_slice(&plaintext).context("Credential file is corrupted")
+    }
+
+    /// Encrypt all entries with a fresh salt and nonce and replace the file atomically.
+    /// 新しいソルトとナンスで全エントリーを暗号化し、ファイルをアトミックに置き換えます。
+    fn save(&self, entries: &BTreeMap<String, String>, material: &[u8]) -> Result<()> {
+        let mut salt = [0u8; SALT_LEN];
+        OsRng.fill_bytes(&mut salt);
+        let key = Self::derive_key(material, &salt, self.params.clone())?;
+        *self.key.borrow_mut() = Some(DerivedKey { salt: salt.to_vec(), params: self.params.clone(), key: key.clone() });
+        let cipher = XChaCha20Poly1305::new(key.as_ref().into());
+        let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let plaintext = Zeroizing::new(serde_json::to_vec(entries)?);
+        let ciphertext = cipher
+            .encrypt(&nonce, plaintext.as_slice())
+            .map_err(|_| anyhow!("Failed to encrypt credentials"))?;
+
+        let envelope = Envelope {
+            version: FORMAT_VERSION,
+            kdf: "argon2id".to_string(),
+            m_cost: self.params.m_cost(),
+            t_cost: self.params.t_cost(),
+            p_cost: self.params.p_cost(),
+            salt: BASE64.encode(salt),
+            nonce: BASE64.encode(nonce),
+            ciphertext: BASE64.encode(ciphertext),
+        };
+        write_atomic(&self.path, serde_json::to_string_pretty(&envelope)?.as_bytes())
+    }
+
+    /// Re-encrypt every entry with a key derived from `new_source`.
+    /// すべてのエントリーを `new_source` から導出した鍵で暗号化し直します。
+    ///
+    /// # Errors
+    /// 現在の鍵で復号できない場合や、ファイルの書き込みに失敗した場合にエラーを返します。
+    pub fn rekey(&self, new_source: KeySource) -> Result<usize> {
+        let _lock = self.lock()?;
+        let entries = self.load()?;
+        let material = Self::resolve_material(&new_source, "New passphrase for the gas credential file", true)?;
+        self.save(&entries, &material)?;
+        *self.material.borrow_mut() = Some(material);
+        Ok(entries.len())
+    }
+
+    fn entry_key(account: &str, kind: SecretKind) -> String {
+        format!("{}:{}", SERVICE_NAME, kind.entry_name(account))
+    }
+}
+
+/// Write `contents` to a uniquely named temporary file next to `path` (mode 0600) and rename it over `path`.
+/// `path` と同じディレクトリの一意な名前の一時ファイル（パーミッション 0600）に書き込み、`path` へリネームします。
+fn write_atomic(path: &Path, contents: &[u8]) -> Result<()> {
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    fs::create_dir_all(parent)?;
+    // NamedTempFile は Unix ではパーミッション 0600 で作成される
+    let mut tmp = tempfile::NamedTempFile::new_in(parent)
+        .with_context(|| format!("Failed to create a temporary file in {}", parent.display()))?;
+    tmp.write_all(contents)?;
+    tmp.as_file().sync_all()?;
+    tmp.persist(path).with_context(|| format!("Failed to replace {}", path.display()))?;
+    Ok(())
+}
+
+/// Create a new key file with 32 random bytes (mode 0600).
+/// 32 バイトの乱数を書き込んだ鍵ファイルを作成します（パーミッション 0600）。
+///
+/// # Errors
+/// ファイルがすでに存在する場合や、書き込みに失敗した場合にエラーを返します。
+pub fn generate_key_file(path: &Path) -> Result<()> {
+    if path.exists() {
+        bail!("Key file already exists: {}", path.display());
+    }
+    let mut key = Zeroizing::new([0u8; 32]);
+    OsRng.fill_bytes(key.as_mut());
+    write_atomic(path, key.as_ref())
+}
+
+impl CredentialStore for EncryptedFileStore {
+    fn set(&self, account: &str, kind: SecretKind, secret: &str) -> Result<()> {
+        let _lock = self.lock()?;
+        let mut entries = self.load()?;
+        entries.insert(Self::entry_key(account, kind), secret.to_string());
+        let material = self.material()?;
+        self.save(&entries, &material)
+    }
+
+    fn get(&self, account: &str, kind: SecretKind) -> Result<String> {
+        self.load()?
+            .remove(&Self::entry_key(account, kind))
+            .ok_or_else(|| NotFound("Password not found in credential file".into()).into())
+    }
+
+    fn delete(&self, account: &str, kind: SecretKind) -> Result<()> {
+        let _lock = self.lock()?;
+        let mut entries = self.load()?;
+        if entries.remove(&Self::entry_key(account, kind)).is_some() {
+            let material = self.material()?;
+            self.save(&entries, &material)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// テストを高速にするための最小限の Argon2 パラメーター
+    fn fast_params() -> Params {
+        Params::new(Params::MIN_M_COST, 1, 1, Some(32)).unwrap()
+    }
+
+    fn passphrase(p: &str) -> KeySource {
+        KeySource::Passphrase(Zeroizing::new(p.to_string()))
+    }
+
+    #[test]
+    fn test_round_trip_and_wrong_passphrase() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("credentials.enc");
+        let store = EncryptedFileStore::with_params(path.clone(), passphrase("correct horse"), fast_params());
+        assert!(crate::credential::is_not_found(&store.get("Work", SecretKind::Pat).unwrap_err()));
+
+        store.set("Work", SecretKind::Pat, "token123").unwrap();
+        store.set("Home", SecretKind::Pat, "token456").unwrap();
+        assert_eq!(store.get("Work", SecretKind::Pat).unwrap(), "token123");
+
+        // 平文は保存されない
+        let raw = fs::read_to_string(&path).unwrap();
+        assert!(!raw.contains("token123"));
+
+        let reopened = EncryptedFileStore::with_params(path.clone(), passphrase("correct horse"), fast_params());
+        assert_eq!(reopened.get("Home", SecretKind::Pat).unwrap(), "token456");
+        reopened.delete("Home", SecretKind::Pat).unwrap();
+        assert!(reopened.get("Home", SecretKind::Pat).is_err());
+
+        let wrong = EncryptedFileStore::with_params(path, passphrase("wrong"), fast_params());
+        let err = wrong.get("Work", SecretKind::Pat).unwrap_err();
+        assert!(err.to_string().contains("Failed to decrypt"));
+        // パスフレーズの誤りは「保存されていない」と区別する
+        assert!(!crate::credential::is_not_found(&err));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_file_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("credentials.enc");
+        let store = EncryptedFileStore::with_params(path.clone(), passphrase("p"), fast_params());
+        store.set("Work", SecretKind::Pat, "token").unwrap();
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+        // 一時ファイルは残らない
+        let names: Vec<_> = fs::read_dir(dir.path()).unwrap().map(|e| e.unwrap().file_name()).collect();
+        assert_eq!(names.len(), 2, "{:?}", names);
+        assert!(dir.path().join("credentials.enc.lock").exists());
+    }
+
+    #[test]
+    fn test_concurrent_writers_keep_every_update() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("credentials.enc");
+        let handles: Vec<_> = (0..4).map(|i| {
+            let path = path.clone();
+            std::thread::spawn(move || {
+                let store = EncryptedFileStore::with_params(path, passphrase("p"), fast_params());
+                store.set(&format!("Account{}", i), SecretKind::Pat, &format!("token{}", i)).unwrap();
+            })
+        }).collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+        let store = EncryptedFileStore::with_params(path, passphrase("p"), fast_params());
+        for i in 0..4 {
+            assert_eq!(store.get(&format!("Account{}", i), SecretKind::Pat).unwrap(), format!("token{}", i));
+        }
+    }
+
+    #[test]
+    fn test_derived_key_is_cached_per_salt() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("credentials.enc");
+        let store = EncryptedFileStore::with_params(path, passphrase("p"), fast_params());
+        store.set("Work", SecretKind::Pat, "token").unwrap();
+        let salt = store.key.borrow().as_ref().unwrap().salt.clone();
+
+        // 保存時に導出した鍵がそのまま読み込みに使われ、元データがなくても復号できる
+        *store.material.borrow_mut() = Some(Zeroizing::new(b"not the passphrase".to_vec()));
+        assert_eq!(store.get("Work", SecretKind::Pat).unwrap(), "token");
+        assert_eq!(store.key.borrow().as_ref().unwrap().salt, salt);
+    }
+
+    #[test]
+    fn test_rekey_to_key_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("credentials.enc");
+        let key_file = dir.path().join("gas.key");
+        fs::write(&key_file, b"0123456789abcdef0123456789abcdef").unwrap();
+
+        let store = EncryptedFileStore::with_params(path.clone(), passphrase("old"), fast_params());
+        store.set("Work", SecretKind::Pat, "token123").unwrap();
+        assert_eq!(store.rekey(KeySource::KeyFile(key_file.clone())).unwrap(), 1);
+
+        let old = EncryptedFileStore::with_params(path.clone(), passphrase("old"), fast_params());
+        assert!(old.get("Work", SecretKind::Pat).is_err());
+        let new = EncryptedFileStore::with_params(path, KeySource::KeyFile(key_file), fast_params());
+        assert_eq!(new.get("Work", SecretKind::Pat).unwrap(), "token123");
+    }
+}
@@ -0,0 +1,110 @@
+use super::{CredentialStore, NotFound, SecretKind};
+use crate::config::AppConfig;
+use anyhow::{Result, bail};
+
+/// Default pattern of the environment variable name / 環境変数名の既定のパターン
+pub const DEFAULT_PATTERN: &str = "GAS_TOKEN_{NICKNAME}";
+
+/// [Production Use] Read-only store that takes secrets from environment variables (e.g. in CI).
+/// 【本番用】環境変数から秘密情報を読み取る読み取り専用の実装（CI 等での利用を想定）。
+pub struct EnvStore {
+    pattern: String,
+    lookup: fn(&str) -> Option<String>,
+}
+
+impl EnvStore {
+    /// Create a store reading variables named by `pattern` from the process environment.
+    /// `pattern` に従った名前の環境変数をプロセスの環境から読み取るストアを作成します。
+    pub fn new(pattern: impl Into<String>) -> Self {
+        Self::with_lookup(pattern, |name| std::env::var(name).ok())
+    }
+
+    /// Create a store with a custom variable lookup (used by tests).
+    /// 環境変数の参照方法を指定してストアを作成します（テスト用）。
+    pub fn with_lookup(pattern: impl Into<String>, lookup: fn(&str) -> Option<String>) -> Self {
+        Self { pattern: pattern.into(), lookup }
+    }
+
+    /// Build the store from the `[env_backend]` section of the settings.
+    /// 設定の `[env_backend]` セクションからストアを作成します。
+    pub fn from_config(config: &AppConfig) -> Self {
+        let pattern = config.env_backend.as_ref()
+            .and_then(|settings| settings.pattern.clone())
+            .unwrap_or_else(|| DEFAULT_PATTERN.to_string());
+        Self::new(pattern)
+    }
+
+    /// Name of the variable holding a secret (`Work` → `GAS_TOKEN_WORK`, OAuth token → `GAS_TOKEN_WORK_OAUTH`).
+    /// 秘密情報を保持する環境変数名を返します（`Work` → `GAS_TOKEN_WORK`、OAuth トークン → `GAS_TOKEN_WORK_OAUTH`）。
+    pub fn variable_name(&self, nickname: &str, kind: SecretKind) -> String {
+        let nickname = kind.entry_name(nickname);
+        let upper: String = nickname.chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_uppercase() } else { '_' })
+            .collect();
+        self.pattern.replace("{NICKNAME}", &upper).replace("{nickname}", &nickname)
+    }
+}
+
+impl CredentialStore for EnvStore {
+    fn set(&self, account: &str, kind: SecretKind, _secret: &str) -> Result<()> {
+        bail!("The env credential backend is read-only; set {} in the environment instead.", self.variable_name(account, kind))
+    }
+
+    fn get(&self, account: &str, kind: SecretKind) -> Result<String> {
+        let name = self.variable_name(account, kind);
+        match (self.lookup)(&name) {
+            Some(secret) if !secret.is_empty() => Ok(secret),
+            _ => Err(NotFound(format!("Environment variable {} is not set", name)).into()),
+        }
+    }
+
+    fn delete(&self, account: &str, kind: SecretKind) -> Result<()> {
+        bail!("The env credential backend is read-only; unset {} in the environment instead.", self.variable_name(account, kind))
+    }
+
+    fn is_read_only(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lookup(name: &str) -> Option<String> {
+        match name {
+            "GAS_TOKEN_WORK" => Some("work-token".into()),
+            "CI_My-Team" => Some("team-token".into()),
+            "GAS_TOKEN_EMPTY" => Some(String::new()),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_variable_name() {
+        let store = EnvStore::new(DEFAULT_PATTERN);
+        assert_eq!(store.variable_name("Work", SecretKind::Pat), "GAS_TOKEN_WORK");
+        assert_eq!(store.variable_name("Work", SecretKind::OAuth), "GAS_TOKEN_WORK_OAUTH");
+        assert_eq!(store.variable_name("my-team.2", SecretKind::Pat), "GAS_TOKEN_MY_TEAM_2");
+        assert_eq!(EnvStore::new("CI_{nickname}").variable_name("My-Team", SecretKind::Pat), "CI_My-Team");
+    }
+
+    #[test]
+    fn test_get_reads_variable() {
+        let store = EnvStore::with_lookup(DEFAULT_PATTERN, lookup);
+        assert_eq!(store.get("Work", SecretKind::Pat).unwrap(), "work-token");
+        let err = store.get("Home", SecretKind::Pat).unwrap_err();
+        assert!(err.to_string().contains("GAS_TOKEN_HOME"));
+        assert!(crate::credential::is_not_found(&err));
+        assert!(store.get("Empty", SecretKind::Pat).is_err());
+        assert_eq!(EnvStore::with_lookup("CI_{nickname}", lookup).get("My-Team", SecretKind::Pat).unwrap(), "team-token");
+    }
+
+    #[test]
+    fn test_set_and_delete_are_refused() {
+        let store = EnvStore::with_lookup(DEFAULT_PATTERN, lookup);
+        let err = store.set("Work", SecretKind::Pat, "x").unwrap_err().to_string();
+        assert!(err.contains("read-only") && err.contains("GAS_TOKEN_WORK"));
+        assert!(store.delete("Work", SecretKind::Pat).is_err());
+    }
+}
@@ -0,0 +1,158 @@
+use crate::config::{AccountConfig, AppConfig, Language, SigningConfig, SigningFormat};
+use crate::i18n::{t, Key};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+/// 診断項目の結果
+#[derive(Debug, Clone, PartialEq)]
+pub enum Status {
+    /// 問題なし
+    Ok,
+    /// ファイルが存在しない（パス）
+    FileNotFound(PathBuf),
+    /// `gpg` / `gpgsm` の秘密鍵一覧に鍵がない（コマンド）
+    SecretKeyNotFound(&'static str),
+    /// コマンドを実行できなかった（コマンド・エラー）
+    CommandFailed(&'static str, String),
+}
+
+/// 診断項目
+#[derive(Debug, Clone, PartialEq)]
+pub enum Item {
+    /// SSH 鍵（パス）
+    SshKey(PathBuf),
+    /// 署名用の鍵（形式・鍵）
+    SigningKey(SigningFormat, String),
+}
+
+/// アカウントごとの診断項目とその結果
+#[derive(Debug, Clone, PartialEq)]
+pub struct Finding {
+    /// 対象のアカウント名
+    pub account: String,
+    /// 診断項目
+    pub item: Item,
+    /// 診断結果
+    pub status: Status,
+}
+
+impl Finding {
+    /// 問題が見つかったかどうかを返します。
+    pub fn is_problem(&self) -> bool {
+        self.status != Status::Ok
+    }
+
+    /// `gas doctor` 向けに診断結果を1行で表します。
+    pub fn describe(&self, lang: &Language) -> String {
+        let item = match &self.item {
+            Item::SshKey(path) => t(lang, Key::DoctorItemSsh).replace("{}", &path.display().to_string()),
+            Item::SigningKey(format, key) => t(lang, Key::DoctorItemSigning).replacen("{}", &format.to_string(), 1).replacen("{}", key, 1),
+        };
+        let reason = match &self.status {
+            Status::Ok => return t(lang, Key::DoctorItemOk).replacen("{}", &self.account, 1).replacen("{}", &item, 1),
+            Status::FileNotFound(path) => t(lang, Key::DoctorFileNotFound).replace("{}", &path.display().to_string()),
+            Status::SecretKeyNotFound(program) => t(lang, Key::DoctorSecretKeyNotFound).replace("{}", program),
+            Status::CommandFailed(program, e) => t(lang, Key::DoctorCommandFailed).replacen("{}", program, 1).replacen("{}", e, 1),
+        };
+        t(lang, Key::DoctorItemProblem).replacen("{}", &self.account, 1).replacen("{}", &item, 1).replacen("{}", &reason, 1)
+    }
+}
+
+/// ファイルが存在するかを確認します。
+fn check_file(path: &Path) -> Status {
+    if path.is_file() {
+        Status::Ok
+    } else {
+        Status::FileNotFound(path.to_path_buf())
+    }
+}
+
+/// `gpg` / `gpgsm` の秘密鍵一覧に指定した鍵が含まれるかを確認します。
+fn check_secret_key(program: &'static str, key: &str) -> Status {
+    let result = Command::new(program)
+        .args(["--batch", "--list-secret-keys", "--", key])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status();
+    match result {
+        Ok(status) if status.success() => Status::Ok,
+        Ok(_) => Status::SecretKeyNotFound(program),
+        Err(e) => Status::CommandFailed(program, e.to_string()),
+    }
+}
+
+/// 署名用の鍵がローカルに存在するかを確認します。
+pub fn check_signing_key(signing: &SigningConfig) -> Status {
+    match signing.format {
+        SigningFormat::Gpg => check_secret_key("gpg", &signing.key),
+        SigningFormat::X509 => check_secret_key("gpgsm", &signing.key),
+        // `key::` で始まる公開鍵は ssh-agent 側の鍵を使用するため、ファイルの確認は行わない
+        SigningFormat::Ssh if signing.key.starts_with("key::") => Status::Ok,
+        SigningFormat::Ssh => check_file(Path::new(&signing.git_key())),
+    }
+}
+
+/// 1つのアカウントの設定（SSH 鍵・署名用の鍵）を診断します。
+pub fn check_account(nickname: &str, account: &AccountConfig) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    if let Some(key) = &account.ssh_key {
+        findings.push(Finding {
+            account: nickname.to_string(),
+            item: Item::SshKey(key.clone()),
+            status: check_file(key),
+        });
+    }
+    if let Some(signing) = &account.signing {
+        findings.push(Finding {
+            account: nickname.to_string(),
+            item: Item::SigningKey(signing.format, signing.key.clone()),
+            status: check_signing_key(signing),
+        });
+    }
+    findings
+}
+
+/// すべてのアカウントを名前順に診断します。
+pub fn run(config: &AppConfig) -> Vec<Finding> {
+    let mut names: Vec<_> = config.accounts.keys().collect();
+    names.sort();
+    names.into_iter()
+        .flat_map(|name| check_account(name, &config.accounts[name]))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_ssh_signing_key() {
+        let dir = tempfile::tempdir().unwrap();
+        let key = dir.path().join("id_ed25519.pub");
+        let signing = SigningConfig { format: SigningFormat::Ssh, key: key.to_string_lossy().into_owned() };
+        assert_eq!(check_signing_key(&signing), Status::FileNotFound(key.clone()));
+
+        std::fs::write(&key, "ssh-ed25519 AAAA test").unwrap();
+        assert_eq!(check_signing_key(&signing), Status::Ok);
+
+        let literal = SigningConfig { format: SigningFormat::Ssh, key: "key::ssh-ed25519 AAAA".into() };
+        assert_eq!(check_signing_key(&literal), Status::Ok);
+    }
+
+    #[test]
+    fn test_run_reports_each_configured_key() {
+        let mut config = AppConfig::default();
+        config.accounts.insert("Work".into(), AccountConfig {
+            ssh_key: Some("/nonexistent/gas_Work_ed25519".into()),
+            signing: Some(SigningConfig { format: SigningFormat::Ssh, key: "/nonexistent/gas_Work_ed25519.pub".into() }),
+            ..AccountConfig::new("workuser")
+        });
+        config.accounts.insert("Home".into(), AccountConfig::new("homeuser"));
+
+        let findings = run(&config);
+        assert_eq!(findings.len(), 2);
+        assert!(findings.iter().all(|f| f.account == "Work" && f.is_problem()));
+        assert_eq!(findings[1].describe(&Language::En), "[!!] Work: signing key (ssh) /nonexistent/gas_Work_ed25519.pub - file not found: /nonexistent/gas_Work_ed25519.pub");
+        assert_eq!(findings[0].describe(&Language::Ja), "[!!] Work: SSH 鍵 /nonexistent/gas_Work_ed25519 - ファイルが見つかりません: /nonexistent/gas_Work_ed25519");
+    }
+}
@@ -0,0 +1,110 @@
+use anyhow::{Context, Result, bail};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// 1日の秒数
+const DAY: u64 = 24 * 60 * 60;
+
+/// `gas list` で期限切れが近いと警告するまでの残り日数
+pub const WARN_DAYS: u64 = 7;
+
+/// 現在時刻を UNIX 時間（秒）で返します。
+pub fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+/// 有効期限の状態
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Status {
+    /// 有効（期限が十分先、または期限なし）
+    Valid,
+    /// 有効だが期限切れが近い（残り日数）
+    ExpiresSoon(u64),
+    /// 期限切れ
+    Expired,
+}
+
+/// 有効期限 `expires_at` の `now` 時点での状態を返します。
+pub fn status(expires_at: Option<u64>, now: u64) -> Status {
+    match expires_at {
+        None => Status::Valid,
+        Some(at) if at <= now => Status::Expired,
+        Some(at) if at - now <= WARN_DAYS * DAY => Status::ExpiresSoon((at - now) / DAY),
+        Some(_) => Status::Valid,
+    }
+}
+
+/// 西暦の年月日から 1970-01-01 からの日数を計算します。
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let m = month as i64;
+    let doy = (153 * (if m > 2 { m - 3 } else { m + 9 }) + 2) / 5 + day as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146097 + doe - 719468
+}
+
+/// 1970-01-01 からの日数を西暦の年月日に変換します。
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+/// `YYYY-MM-DD` 形式の日付を、その日の 00:00:00 UTC の UNIX 時間に変換します。
+///
+/// # Errors
+/// 形式が正しくない場合や、1970 年より前の日付の場合にエラーを返します。
+pub fn parse_date(text: &str) -> Result<u64> {
+    let parts: Vec<&str> = text.trim().split('-').collect();
+    let [year, month, day] = parts.as_slice() else {
+        bail!("Invalid date '{}': expected YYYY-MM-DD", text);
+    };
+    let year: i64 = year.parse().with_context(|| format!("Invalid year in '{}'", text))?;
+    let month: u32 = month.parse().with_context(|| format!("Invalid month in '{}'", text))?;
+    let day: u32 = day.parse().with_context(|| format!("Invalid day in '{}'", text))?;
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        bail!("Invalid date '{}'", text);
+    }
+    let days = days_from_civil(year, month, day);
+    if days < 0 || civil_from_days(days) != (year, month, day) {
+        bail!("Invalid date '{}'", text);
+    }
+    Ok(days as u64 * DAY)
+}
+
+/// UNIX 時間を `YYYY-MM-DD` 形式（UTC）に変換します。
+pub fn format_date(timestamp: u64) -> String {
+    let (year, month, day) = civil_from_days((timestamp / DAY) as i64);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_format_date() {
+        assert_eq!(parse_date("1970-01-01").unwrap(), 0);
+        assert_eq!(parse_date("2024-02-29").unwrap(), 1_709_164_800);
+        assert_eq!(format_date(1_709_164_800 + 3600), "2024-02-29");
+        assert!(parse_date("2023-02-29").is_err());
+        assert!(parse_date("2024/01/01").is_err());
+    }
+
+    #[test]
+    fn test_status() {
+        let now = 1_000_000_000;
+        assert_eq!(status(None, now), Status::Valid);
+        assert_eq!(status(Some(now), now), Status::Expired);
+        assert_eq!(status(Some(now + 3 * DAY + 10), now), Status::ExpiresSoon(3));
+        assert_eq!(status(Some(now + 30 * DAY), now), Status::Valid);
+    }
+}
@@ -0,0 +1,309 @@
+use crate::config::{AccountConfig, AppConfig, safe_name};
+use crate::path_match::{self, PathStyle, REGEX_PREFIX};
+use crate::ssh;
+use anyhow::{Context, Result, bail};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// 管理ファイルの先頭に付与するヘッダー
+const HEADER: &str = "# Managed by gas. Do not edit: this file is regenerated from config.toml.\n";
+
+/// `~/.gitconfig` から読み込まれる管理ファイルのファイル名（設定ディレクトリ内）
+pub const INDEX_FILE: &str = "gitconfig";
+
+/// アカウントごとの設定断片を置くディレクトリ名（設定ディレクトリ内）
+pub const ACCOUNTS_DIR: &str = "accounts";
+
+/// アカウントのニックネームから設定断片のファイル名を作成します。
+///
+/// ファイル名に使えない文字は `_` に置き換えます。
+pub fn fragment_file_name(nickname: &str) -> String {
+    format!("{}.gitconfig", safe_name(nickname))
+}
+
+/// Git の設定ファイルの値として書き出せるよう、ダブルクォートで囲んでエスケープします。
+fn quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// アカウントの設定断片に書き出す `(セクション, キー, 値)` の一覧を返します。
+fn fragment_entries(account: &AccountConfig) -> Vec<(String, &'static str, String)> {
+    let mut entries = Vec::new();
+    if let Some(name) = &account.name {
+        entries.push(("user".to_string(), "name", name.clone()));
+    }
+    if let Some(email) = &account.email {
+        entries.push(("user".to_string(), "email", email.clone()));
+    }
+    if let Some(signing) = &account.signing {
+        entries.push(("user".to_string(), "signingkey", signing.git_key()));
+    }
+    if let Some(key) = &account.ssh_key {
+        entries.push(("core".to_string(), "sshCommand", ssh::ssh_command(key)));
+    }
+    if let Some(signing) = &account.signing {
+        entries.push(("gpg".to_string(), "format", signing.format.git_value().to_string()));
+        entries.push(("commit".to_string(), "gpgsign", "true".to_string()));
+    }
+    entries
+}
+
+/// アカウントの設定断片（コミット用の名前・メールアドレス等）を生成します。
+pub fn render_fragment(account: &AccountConfig) -> String {
+    let mut out = String::from(HEADER);
+    let mut current: Option<String> = None;
+    for (section, key, value) in fragment_entries(account) {
+        if current.as_ref() != Some(&section) {
+            out.push_str(&format!("[{}]\n", section));
+            current = Some(section);
+        }
+        out.push_str(&format!("\t{} = {}\n", key, quote(&value)));
+    }
+    out
+}
+
+/// `path_rules` のキーを `includeIf` の `gitdir:` 条件に変換します。
+///
+/// 正規表現ルールは `gitdir:` で表現できないため `None` を返します。
+/// 相対パスは資格情報の判定と同じくホームディレクトリ（`path_match::rule_base`）を基準に解決します。
+/// パスとグロブは配下のリポジトリにも一致するよう末尾に `/` または `/**` を付与し、
+/// 大文字・小文字を区別しない OS では `gitdir/i:` を使用します。
+pub fn gitdir_condition(rule: &str, style: PathStyle) -> Option<String> {
+    if rule.starts_with(REGEX_PREFIX) {
+        return None;
+    }
+    let keyword = if style.case_sensitive { "gitdir" } else { "gitdir/i" };
+    let pattern = if rule.contains(['*', '?', '[', '{']) {
+        let expanded = path_match::expand_tilde(rule).to_string_lossy().to_string();
+        let absolute = if style.is_absolute(&expanded) {
+            expanded
+        } else {
+            path_match::rule_base().join(expanded).to_string_lossy().to_string()
+        };
+        let absolute = style.slashed(&absolute);
+        if absolute.ends_with("**") { absolute } else { format!("{}/**", absolute.trim_end_matches('/')) }
+    } else {
+        let canonical = style.slashed(&path_match::canonicalize(rule, &path_match::rule_base()));
+        format!("{}/", canonical.trim_end_matches('/'))
+    };
+    Some(format!("{}:{}", keyword, pattern))
+}
+
+/// `path_rules` から `includeIf` ブロックの一覧（管理ファイルの内容）を生成します。
+///
+/// Git は後から読み込んだ設定を優先するため、具体性の低いルールから順に出力し、
+/// 最も具体的なルールが最後に適用されるようにします。
+pub fn render_index(config: &AppConfig, accounts_dir: &Path, style: PathStyle) -> String {
+    let home = path_match::rule_base();
+    let mut rules: Vec<_> = config.path_rules.iter()
+        .filter(|(_, rule)| config.accounts.contains_key(rule.account()))
+        .map(|(key, rule)| {
+            let rank = path_match::PathPattern::parse(key, &home, style)
+                .map(|p| (p.specificity(style), rule.priority()))
+                .unwrap_or_default();
+            (rank, key, rule)
+        })
+        .collect();
+    rules.sort_by(|(ra, ka, _), (rb, kb, _)| ra.cmp(rb).then_with(|| kb.cmp(ka)));
+
+    let mut out = String::from(HEADER);
+    for (_, key, rule) in rules {
+        match gitdir_condition(key, style) {
+            Some(condition) => {
+                let fragment = accounts_dir.join(fragment_file_name(rule.account()));
+                out.push_str(&format!("[includeIf {}]\n", quote(&condition)));
+                out.push_str(&format!("\tpath = {}\n", quote(&style.slashed(&fragment.to_string_lossy()))));
+            }
+            None => out.push_str(&format!("# skipped (regex rules cannot be expressed as gitdir): {}\n", key)),
+        }
+    }
+    out
+}
+
+/// 内容が変わる場合のみファイルを書き込みます。
+///
+/// # Returns
+/// 書き込んだ場合は `true` を返します。
+fn write_if_changed(path: &Path, content: &str) -> Result<bool> {
+    if fs::read_to_string(path).ok().as_deref() == Some(content) {
+        return Ok(false);
+    }
+    fs::write(path, content).with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(true)
+}
+
+/// 管理ファイルとアカウントごとの設定断片を `dir` に書き出し、削除されたアカウントの断片を消去します。
+///
+/// 同じ設定から何度実行しても同じ内容になり、内容が変わらないファイルは書き換えません。
+///
+/// # Returns
+/// 管理ファイル（`dir/gitconfig`）のパスを返します。
+///
+/// # Errors
+/// ファイルの書き込みまたは削除に失敗した場合にエラーを返します。
+pub fn write_managed_files(config: &AppConfig, dir: &Path) -> Result<PathBuf> {
+    let style = PathStyle::native();
+    let accounts_dir = dir.join(ACCOUNTS_DIR);
+    fs::create_dir_all(&accounts_dir)?;
+
+    let mut expected = Vec::new();
+    for (nickname, account) in &config.accounts {
+        let file_name = fragment_file_name(nickname);
+        write_if_changed(&accounts_dir.join(&file_name), &render_fragment(account))?;
+        expected.push(file_name);
+    }
+    for entry in fs::read_dir(&accounts_dir)? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().to_string();
+        if name.ends_with(".gitconfig") && !expected.contains(&name) {
+            fs::remove_file(entry.path())?;
+        }
+    }
+
+    let index = dir.join(INDEX_FILE);
+    write_if_changed(&index, &render_index(config, &accounts_dir, style))?;
+    Ok(index)
+}
+
+/// Git の設定ファイルに `include.path` として `include` が登録されていなければ追加します。
+///
+/// 既存のエントリーには一切触れず、`git config --add` で 1 行追加するだけです。
+///
+/// # Arguments
+/// * `scope` - `git config` に渡す対象の指定（`["--global"]` や `["--file", path]`）
+/// * `include` - 読み込ませるファイルのパス
+///
+/// # Returns
+/// 追加した場合は `true` を返します。
+///
+/// # Errors
+/// Git コマンドの実行に失敗した場合にエラーを返します。
+pub fn ensure_include(scope: &[&str], include: &Path) -> Result<bool> {
+    let include = PathStyle::native().slashed(&include.to_string_lossy());
+    let out = Command::new("git")
+        .arg("config").args(scope)
+        .args(["--get-all", "include.path"])
+        .output()
+        .context("Failed to execute git command.")?;
+    if String::from_utf8_lossy(&out.stdout).lines().any(|line| line.trim() == include) {
+        return Ok(false);
+    }
+    let status = Command::new("git")
+        .arg("config").args(scope)
+        .args(["--add", "include.path", &include])
+        .status()
+        .context("Failed to execute git command.")?;
+    if !status.success() { bail!("git config --add include.path failed"); }
+    Ok(true)
+}
+
+/// 管理ファイルを再生成し、グローバルな Git 設定から読み込まれるようにします。
+///
+/// `gas add` / `gas use` / `gas remove` の後に呼び出されます。
+///
+/// # Errors
+/// ファイルの書き込みや Git コマンドの実行に失敗した場合にエラーを返します。
+pub fn sync(config: &AppConfig) -> Result<()> {
+    let index = write_managed_files(config, &AppConfig::config_dir()?)?;
+    ensure_include(&["--global"], &index)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{SigningConfig, SigningFormat};
+    use tempfile::tempdir;
+
+    fn work_account() -> AccountConfig {
+        AccountConfig {
+            name: Some("Work \"User\"".into()),
+            email: Some("work@example.com".into()),
+            ..AccountConfig::new("workuser")
+        }
+    }
+
+    #[test]
+    fn test_render_fragment() {
+        let fragment = render_fragment(&work_account());
+        assert!(fragment.starts_with(HEADER));
+        assert!(fragment.contains("[user]\n\tname = \"Work \\\"User\\\"\"\n\temail = \"work@example.com\"\n"));
+
+        let account = AccountConfig { ssh_key: Some(PathBuf::from("/k/id")), ..work_account() };
+        assert!(render_fragment(&account).contains("[core]\n\tsshCommand = \"ssh -i \\\"/k/id\\\" -o IdentitiesOnly=yes\"\n"));
+
+        let signing = SigningConfig { format: SigningFormat::Gpg, key: "ABCD1234".into() };
+        let account = AccountConfig { signing: Some(signing), ..work_account() };
+        let fragment = render_fragment(&account);
+        assert!(fragment.contains("\temail = \"work@example.com\"\n\tsigningkey = \"ABCD1234\"\n"));
+        assert!(fragment.contains("[gpg]\n\tformat = \"openpgp\"\n[commit]\n\tgpgsign = \"true\"\n"));
+    }
+
+    #[test]
+    fn test_gitdir_condition() {
+        let style = PathStyle::POSIX;
+        assert_eq!(gitdir_condition("/src/work", style).as_deref(), Some("gitdir:/src/work/"));
+        assert_eq!(gitdir_condition("/src/work-*", style).as_deref(), Some("gitdir:/src/work-*/**"));
+        assert_eq!(gitdir_condition("/src/work-*/**", style).as_deref(), Some("gitdir:/src/work-*/**"));
+        assert_eq!(gitdir_condition("re:^/srv", style), None);
+        assert_eq!(gitdir_condition("/src/work", PathStyle::MACOS).as_deref(), Some("gitdir/i:/src/work/"));
+
+        // 相対パスは resolve と同じくホームディレクトリを基準にする
+        let home = style.slashed(&path_match::rule_base().to_string_lossy());
+        assert_eq!(gitdir_condition("src/work-*", style), Some(format!("gitdir:{}/src/work-*/**", home)));
+    }
+
+    #[test]
+    fn test_render_index_orders_by_specificity() {
+        let mut config = AppConfig::default();
+        config.accounts.insert("Work".into(), work_account());
+        config.accounts.insert("Client".into(), AccountConfig::new("client"));
+        config.path_rules.insert("/src/work/client".into(), "Client".into());
+        config.path_rules.insert("/src/work".into(), "Work".into());
+        config.path_rules.insert("re:^/srv".into(), "Work".into());
+        config.path_rules.insert("/src/unknown".into(), "Missing".into());
+
+        let index = render_index(&config, Path::new("/cfg/accounts"), PathStyle::POSIX);
+        let work = index.find("[includeIf \"gitdir:/src/work/\"]\n\tpath = \"/cfg/accounts/Work.gitconfig\"").unwrap();
+        let client = index.find("[includeIf \"gitdir:/src/work/client/\"]\n\tpath = \"/cfg/accounts/Client.gitconfig\"").unwrap();
+        assert!(work < client);
+        assert!(index.contains("# skipped (regex rules cannot be expressed as gitdir): re:^/srv"));
+        assert!(!index.contains("unknown"));
+    }
+
+    #[test]
+    fn test_write_managed_files_is_idempotent_and_prunes() {
+        let dir = tempdir().unwrap();
+        let mut config = AppConfig::default();
+        config.accounts.insert("Work".into(), work_account());
+        config.accounts.insert("Old".into(), AccountConfig::new("old"));
+        config.path_rules.insert("/src/work".into(), "Work".into());
+
+        let index = write_managed_files(&config, dir.path()).unwrap();
+        let first = fs::read_to_string(&index).unwrap();
+        assert!(dir.path().join("accounts/Old.gitconfig").exists());
+
+        config.accounts.remove("Old");
+        write_managed_files(&config, dir.path()).unwrap();
+        assert_eq!(fs::read_to_string(&index).unwrap(), first);
+        assert!(!dir.path().join("accounts/Old.gitconfig").exists());
+        assert!(dir.path().join("accounts/Work.gitconfig").exists());
+    }
+
+    #[test]
+    fn test_ensure_include_keeps_existing_entries() {
+        let dir = tempdir().unwrap();
+        let gitconfig = dir.path().join(".gitconfig");
+        fs::write(&gitconfig, "[user]\n\tname = Me\n[include]\n\tpath = /other/file\n").unwrap();
+        let file = gitconfig.to_string_lossy().to_string();
+        let scope = ["--file", file.as_str()];
+
+        assert!(ensure_include(&scope, Path::new("/cfg/gitconfig")).unwrap());
+        assert!(!ensure_include(&scope, Path::new("/cfg/gitconfig")).unwrap());
+        let content = fs::read_to_string(&gitconfig).unwrap();
+        assert!(content.contains("name = Me"));
+        assert!(content.contains("path = /other/file"));
+        assert_eq!(content.matches("/cfg/gitconfig").count(), 1);
+    }
+}
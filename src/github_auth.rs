@@ -1,67 +1,135 @@
+use crate::auth::{self, AccessToken, AuthCodeClient, AuthProvider, DeviceAuthorization, Flow, Identity, TokenInfo};
+use crate::config::{AppConfig, ProviderConfig};
 use anyhow::{Context, Result, bail};
 use serde::Deserialize;
-use std::thread;
-use std::time::Duration;
 use reqwest::blocking::Client;
 
 /// GitHub OAuth App client ID. / GitHub の OAuth App クライアント ID。
 /// Used for device flow authentication (browser authentication). / デバイスフローによる認証（ブラウザ認証）に使用されます。
 pub const CLIENT_ID: &str = "Ov23li6WaAMnOZW2RXsa"; 
 
-#[derive(Deserialize)]
-struct DeviceCodeResponse {
-    device_code: String,
-    user_code: String,
-    verification_uri: String,
-    interval: u64,
+/// Host name of GitHub.com. / GitHub.com のホスト名。
+const GITHUB_HOST: &str = "github.com";
+
+/// Scopes requested by default. / 既定で要求するスコープ。
+const DEFAULT_SCOPES: [&str; 2] = ["repo", "read:user"];
+
+/// Web / API URLs and OAuth client of a GitHub instance. / GitHub インスタンスの Web・API の URL と OAuth クライアント。
+#[derive(Debug, Clone, PartialEq)]
+pub struct Endpoints {
+    /// Web URL without a trailing slash (e.g. `https://github.com`) / 末尾のスラッシュを除いた Web の URL
+    pub web_url: String,
+    /// API URL without a trailing slash (e.g. `https://api.github.com`) / 末尾のスラッシュを除いた API の URL
+    pub api_url: String,
+    /// OAuth App client ID / OAuth App のクライアント ID
+    pub client_id: String,
+    /// OAuth App client secret (needed to revoke and refresh tokens and for the browser sign-in)
+    /// OAuth App のクライアントシークレット（トークンの失効・更新とブラウザでのサインインに必要）
+    pub client_secret: Option<String>,
+}
+
+impl Endpoints {
+    /// Endpoints of GitHub.com. / GitHub.com のエンドポイント。
+    pub fn github() -> Self {
+        Self {
+            web_url: "https://github.com".into(),
+            api_url: "https://api.github.com".into(),
+            client_id: CLIENT_ID.into(),
+            client_secret: None,
+        }
+    }
+
+    /// Endpoints for `host`, applying the `[providers."<host>"]` section of the settings.
+    /// `[providers."<host>"]` の設定を反映した `host` のエンドポイントを返します。
+    ///
+    /// Unset URLs default to those of GitHub.com for `github.com`, and to `https://<host>` and
+    /// `https://<host>/api/v3` (GitHub Enterprise Server) otherwise.
+    /// 未指定の URL は、`github.com` では GitHub.com のものを、それ以外では `https://<host>` と
+    /// `https://<host>/api/v3`（GitHub Enterprise Server）を使用します。
+    ///
+    /// # Errors
+    /// Returns an error if no client ID is configured for a host other than `github.com`.
+    /// `github.com` 以外のホストでクライアント ID が設定されていない場合にエラーを返します。
+    pub fn for_host(config: &AppConfig, host: &str) -> Result<Self> {
+        let settings = config.providers.get(host).cloned().unwrap_or_default();
+        let defaults = if host == GITHUB_HOST { Some(Self::github()) } else { None };
+        let web_url = settings.web_url
+            .or_else(|| defaults.as_ref().map(|d| d.web_url.clone()))
+            .unwrap_or_else(|| format!("https://{}", host));
+        let web_url = auth::trim_url(&web_url);
+        let api_url = settings.api_url
+            .or_else(|| defaults.as_ref().map(|d| d.api_url.clone()))
+            .unwrap_or_else(|| format!("{}/api/v3", web_url));
+        let client_id = settings.client_id
+            .or_else(|| defaults.map(|d| d.client_id))
+            .with_context(|| format!("No OAuth client ID is configured for {}: set client_id in [providers.\"{}\"] of config.toml", host, host))?;
+        Ok(Self { web_url, api_url: auth::trim_url(&api_url), client_id, client_secret: settings.client_secret })
+    }
+
+    fn device_code_url(&self) -> String {
+        format!("{}/login/device/code", self.web_url)
+    }
+
+    fn token_url(&self) -> String {
+        format!("{}/login/oauth/access_token", self.web_url)
+    }
+
+    fn user_url(&self) -> String {
+        format!("{}/user", self.api_url)
+    }
+}
+
+/// Profile of the authenticated GitHub user. / 認証された GitHub ユーザーのプロフィール。
+#[derive(Debug, Deserialize)]
+pub struct GitHubUser {
+    /// Login name / ログイン名
+    pub login: String,
+    /// Numeric user ID / 数値のユーザー ID
+    pub id: u64,
+    /// Display name (if set) / 表示名（設定されている場合）
+    pub name: Option<String>,
+    /// Public email address (if set) / 公開メールアドレス（設定されている場合）
+    pub email: Option<String>,
 }
 
-#[derive(Deserialize)]
-struct AccessTokenResponse {
-    access_token: Option<String>,
-    error: Option<String>,
+impl GitHubUser {
+    /// Returns the email to use for commits, falling back to the GitHub noreply address.
+    /// コミットに使用するメールアドレスを返します。公開メールアドレスがない場合は GitHub の noreply アドレスを使用します。
+    pub fn commit_email(&self) -> String {
+        match &self.email {
+            Some(email) if !email.is_empty() => email.clone(),
+            _ => format!("{}+{}@users.noreply.github.com", self.id, self.login),
+        }
+    }
 }
 
-#[derive(Deserialize)]
-struct UserResponse {
-    login: String,
+impl From<GitHubUser> for Identity {
+    fn from(user: GitHubUser) -> Self {
+        let email = user.commit_email();
+        Self { name: user.name.or_else(|| Some(user.login.clone())), email: Some(email), login: user.login }
+    }
 }
 
 /// Initiate the GitHub Device Flow (OAuth 2.0) authentication process.
 /// 
 /// Obtain the user code for the user to enter in the browser and the device code required for polling.
-/// 
-/// # Returns
-/// Returns the following tuple:
-/// - `device_code`: Identifier for polling
-/// - `user_code`: 8-digit code the user enters on GitHub
-/// - `verification_uri`: URL the user accesses
-/// - `interval`: Polling interval (seconds)
+/// `scopes` are the space-separated OAuth scopes to request.
 ///
 /// # Errors
-/// Returns an error if communication with the GitHub API fails or if the client ID is not configured.
+/// Returns an error if communication with the GitHub API fails.
 /// -----------------------------------------------------------------------------------------------------
 /// GitHub の Device Flow（OAuth 2.0）による認証プロセスを開始します。
 /// 
-/// ユーザーがブラウザで入力するためのユーザーコードや、ポーリングに必要なデバイスコードを取得します。
-/// 
-/// # Returns
-/// 以下のタプルの結果を返します：
-/// - `device_code`: ポーリング用の識別子
-/// - `user_code`: ユーザーが GitHub 上で入力する 8 桁のコード
-/// - `verification_uri`: ユーザーがアクセスする URL
-/// - `interval`: ポーリング間隔（秒）
+/// ユーザーがブラウザで入力するためのユーザーコード（8 桁）や、ポーリングに必要なデバイスコードを取得します。
+/// `scopes` には要求する OAuth スコープを空白区切りで指定します。
 ///
 /// # Errors
-/// GitHub API との通信に失敗した場合や、クライアント ID が未設定の場合にエラーを返します。
-pub fn start_device_flow() -> Result<(String, String, String, u64)> {
-    if CLIENT_ID == "YOUR_CLIENT_ID_HERE" {
-        bail!("Client ID is not configured in source code.");
-    }
+/// GitHub API との通信に失敗した場合にエラーを返します。
+pub fn start_device_flow(endpoints: &Endpoints, scopes: &str) -> Result<DeviceAuthorization> {
     let client = Client::new();
-    let res = client.post("https://github.com/login/device/code")
+    let res = client.post(endpoints.device_code_url())
         .header("Accept", "application/json")
-        .form(&[("client_id", CLIENT_ID), ("scope", "repo read:user")])
+        .form(&[("client_id", endpoints.client_id.as_str()), ("scope", scopes)])
         .send()
         .context("Failed to connect to GitHub")?;
 
@@ -70,15 +138,13 @@ pub fn start_device_flow() -> Result<(String, String, String, u64)> {
         let error_text = res.text().unwrap_or_default();
         bail!("GitHub API Error: {} - {}", status, error_text);
     }
-    let body: DeviceCodeResponse = res.json().context("Failed to parse device code response")?;
-    Ok((body.device_code, body.user_code, body.verification_uri, body.interval))
+    res.json().context("Failed to parse device code response")
 }
 
 /// Attempts to obtain an access token (polling) until the user completes authentication in the browser.
 ///
 /// # Arguments
-/// * `device_code` - The device code obtained from `start_device_flow`
-/// * `interval` - The recommended polling interval
+/// * `device` - The device authorization obtained from `start_device_flow`
 ///
 /// # Errors
 /// Returns an error if the request times out or the user explicitly denies access.
@@ -86,67 +152,292 @@ pub fn start_device_flow() -> Result<(String, String, String, u64)> {
 /// ユーザーがブラウザで認証を完了するまで、アクセストークンの取得を試行（ポーリング）します。
 ///
 /// # Arguments
-/// * `device_code` - `start_device_flow` で取得したデバイスコード
-/// * `interval` - 推奨されるポーリング間隔
+/// * `device` - `start_device_flow` で取得したデバイス認可
 ///
 /// # Errors
 /// タイムアウトした場合や、ユーザーが明示的に拒否した場合にエラーを返します。
-pub fn poll_for_token(device_code: &str, interval: u64) -> Result<String> {
-    let client = Client::new();
-    let url = "https://github.com/login/oauth/access_token";
-    let wait_time = Duration::from_secs(interval + 1);
-
-    for _ in 0..100 {
-        thread::sleep(wait_time);
-        let res = client.post(url)
-            .header("Accept", "application/json")
-            .form(&[
-                ("client_id", CLIENT_ID),
-                ("device_code", device_code),
-                ("grant_type", "urn:ietf:params:oauth:grant-type:device_code")
-            ])
-            .send()?;
-
-        if res.status().is_success() {
-            let body: AccessTokenResponse = res.json()?;
-            if let Some(token) = body.access_token {
-                return Ok(token);
-            }
-            if let Some(err) = body.error {
-                if err == "authorization_pending" { continue; }
-                else if err == "slow_down" { thread::sleep(Duration::from_secs(5)); continue; }
-                else { bail!("Authorization error: {}", err); }
-            }
-        } else {
-             let status = res.status();
-             let error_text = res.text().unwrap_or_default();
-             bail!("GitHub Token Error: {} - {}", status, error_text);
-        }
-    }
-    bail!("Timeout waiting for authorization.");
+pub fn poll_for_token(endpoints: &Endpoints, device: &DeviceAuthorization) -> Result<AccessToken> {
+    auth::poll_device_token(&endpoints.token_url(), &endpoints.client_id, device)
+}
+
+/// Exchanges a refresh token for a new access token (and a rotated refresh token).
+///
+/// GitHub requires the client secret for this grant, so it only works with a GitHub App whose `client_id` and
+/// `client_secret` are configured in `[providers]`. The built-in app (`CLIENT_ID`) has no secret and cannot refresh.
+///
+/// # Errors
+/// Returns an error if the refresh token is invalid or expired, or if communication with GitHub fails.
+/// -----------------------------------------------------------------------------------------------------
+/// リフレッシュトークンを新しいアクセストークン（およびローテーションされたリフレッシュトークン）と交換します。
+///
+/// GitHub ではクライアントシークレットが必須のため、`[providers]` に `client_id` と `client_secret` を設定した
+/// GitHub App でのみ使用できます。gas 組み込みのアプリ（`CLIENT_ID`）はシークレットを持たないため更新できません。
+///
+/// # Errors
+/// リフレッシュトークンが無効・期限切れの場合や、GitHub との通信に失敗した場合にエラーを返します。
+pub fn refresh_access_token(endpoints: &Endpoints, refresh_token: &str) -> Result<AccessToken> {
+    auth::refresh_token(&endpoints.token_url(), &endpoints.client_id, endpoints.client_secret.as_deref(), refresh_token)
 }
 
-/// Retrieves the username from GitHub using the specified access token.
+/// Retrieves the profile (login, display name, email) of the user that owns the access token.
 ///
 /// # Errors
 /// Returns an error if the token is invalid or if communication with the API fails.
 /// -----------------------------------------------------------------------------------------------------
-/// 指定されたアクセストークンを使用して、GitHub からユーザー名を取得します。
+/// 指定されたアクセストークンの所有者のプロフィール（ログイン名・表示名・メールアドレス）を取得します。
 ///
 /// # Errors
 /// トークンが無効な場合や、API との通信に失敗した場合にエラーを返します。
-pub fn get_username(token: &str) -> Result<String> {
-    let client = Client::new();
-    let res = client.get("https://api.github.com/user")
-        .header("User-Agent", "gas-cli")
-        .header("Authorization", format!("token {}", token))
+pub fn get_user(endpoints: &Endpoints, token: &str) -> Result<GitHubUser> {
+    fetch_user(endpoints, token)?
+        .map(|(user, _)| user)
+        .context("GitHub rejected the access token")
+}
+
+/// `GET /user` を送信し、ユーザーと付与されたスコープを返します。トークンが拒否された場合は `None` を返します。
+fn fetch_user(endpoints: &Endpoints, token: &str) -> Result<Option<(GitHubUser, Option<Vec<String>>)>> {
+    let request = Client::new().get(endpoints.user_url())
+        .header("Authorization", format!("token {}", token));
+    auth::fetch_authorized(request)
+}
+
+/// Revokes an access token of the OAuth App (`DELETE /applications/{client_id}/token`).
+///
+/// # Errors
+/// Returns an error if no client secret is configured or if GitHub rejects the request.
+/// A token that GitHub does not know (404, e.g. already revoked in the browser) is treated as revoked.
+/// -----------------------------------------------------------------------------------------------------
+/// OAuth App のアクセストークンを失効させます（`DELETE /applications/{client_id}/token`）。
+///
+/// # Errors
+/// クライアントシークレットが設定されていない場合や、GitHub がリクエストを拒否した場合にエラーを返します。
+/// GitHub が見つけられないトークン（404。ブラウザで失効させた後など）は失効済みとして扱います。
+pub fn revoke_token(endpoints: &Endpoints, token: &str) -> Result<()> {
+    let secret = endpoints.client_secret.as_deref()
+        .context("Revoking a GitHub token requires client_secret of the OAuth App in [providers] of config.toml")?;
+    let res = Client::new().delete(format!("{}/applications/{}/token", endpoints.api_url, endpoints.client_id))
+        .header("User-Agent", auth::USER_AGENT)
+        .header("Accept", "application/vnd.github+json")
+        .basic_auth(&endpoints.client_id, Some(secret))
+        .json(&serde_json::json!({ "access_token": token }))
         .send()
-        .context("Failed to get user info")?;
+        .context("Failed to connect to GitHub")?;
+    match res.status().as_u16() {
+        // 404 は既に失効しているトークン（利用者がブラウザで取り消した場合など）
+        204 | 404 => Ok(()),
+        status => bail!("GitHub API Error: {} - {}", status, res.text().unwrap_or_default()),
+    }
+}
 
-    if !res.status().is_success() {
-        bail!("User Info Error: {}", res.status());
+/// GitHub / GitHub Enterprise Server provider (device flow). / GitHub・GitHub Enterprise Server のプロバイダー（デバイスフロー）。
+pub struct GitHub {
+    endpoints: Endpoints,
+    redirect_port: u16,
+    scopes: Vec<String>,
+}
+
+impl GitHub {
+    /// Create a provider for the given endpoints, taking the loopback port and scopes from `settings`.
+    /// 指定したエンドポイントのプロバイダーを作成します。ループバックのポートとスコープは `settings` から取得します。
+    pub fn new(endpoints: Endpoints, settings: &ProviderConfig) -> Self {
+        Self {
+            endpoints,
+            redirect_port: settings.redirect_port.unwrap_or(0),
+            scopes: settings.scopes.clone().unwrap_or_else(|| DEFAULT_SCOPES.map(String::from).to_vec()),
+        }
+    }
+}
+
+impl AuthProvider for GitHub {
+    fn name(&self) -> &'static str {
+        "GitHub"
+    }
+
+    fn start(&self) -> Result<Flow> {
+        start_device_flow(&self.endpoints, &self.scopes.join(" ")).map(Flow::Device)
+    }
+
+    fn authorization_code(&self) -> Option<AuthCodeClient> {
+        Some(AuthCodeClient {
+            authorize_url: format!("{}/login/oauth/authorize", self.endpoints.web_url),
+            token_url: self.endpoints.token_url(),
+            client_id: self.endpoints.client_id.clone(),
+            // GitHub はコードの交換にクライアントシークレットを要求するため、未設定ならこのフローは提供しない
+            client_secret: Some(self.endpoints.client_secret.clone()?),
+            scopes: self.scopes.join(" "),
+            redirect_port: self.redirect_port,
+        })
+    }
+
+    fn scopes(&self) -> &[String] {
+        &self.scopes
+    }
+
+    fn poll(&self, device: &DeviceAuthorization) -> Result<AccessToken> {
+        poll_for_token(&self.endpoints, device)
+    }
+
+    fn identity(&self, _login: Option<&str>, secret: &str) -> Result<Identity> {
+        get_user(&self.endpoints, secret).map(Identity::from)
+    }
+
+    fn validate(&self, _login: Option<&str>, secret: &str) -> Result<Option<TokenInfo>> {
+        Ok(fetch_user(&self.endpoints, secret)?
+            .map(|(user, scopes)| TokenInfo { identity: user.into(), scopes }))
+    }
+
+    fn refresh(&self, refresh_token: &str) -> Result<AccessToken> {
+        refresh_access_token(&self.endpoints, refresh_token)
+    }
+
+    fn revoke(&self, secret: &str) -> Result<()> {
+        revoke_token(&self.endpoints, secret)
+    }
+
+    fn can_revoke(&self) -> bool {
+        // 失効の API はクライアントシークレットによる認証が必要
+        self.endpoints.client_secret.is_some()
     }
 
-    let user: UserResponse = res.json().context("Failed to parse user info")?;
-    Ok(user.login)
+    fn revocation_url(&self) -> String {
+        format!("{}/settings/applications", self.endpoints.web_url)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::test_server::{self, Received, Reply};
+    use crate::config::ProviderConfig;
+    use std::thread;
+
+    #[test]
+    fn test_commit_email_falls_back_to_noreply() {
+        let mut user = GitHubUser { login: "octocat".into(), id: 583231, name: None, email: None };
+        assert_eq!(user.commit_email(), "583231+octocat@users.noreply.github.com");
+        user.email = Some("octo@example.com".into());
+        assert_eq!(user.commit_email(), "octo@example.com");
+    }
+
+    /// 指定した応答を順に返すモックサーバーを起動し、そのエンドポイントとハンドルを返します。
+    fn mock_server(responses: Vec<Reply>) -> (Endpoints, thread::JoinHandle<Vec<Received>>) {
+        let (base, handle) = test_server::start(responses);
+        let endpoints = Endpoints {
+            web_url: base.clone(),
+            api_url: format!("{}/api/v3", base),
+            client_id: "test-client".into(),
+            client_secret: Some("test-secret".into()),
+        };
+        (endpoints, handle)
+    }
+
+    #[test]
+    fn test_endpoints_for_host() {
+        let mut config = AppConfig::default();
+        assert_eq!(Endpoints::for_host(&config, "github.com").unwrap(), Endpoints::github());
+        assert!(Endpoints::for_host(&config, "ghe.example.com").is_err());
+
+        config.providers.insert("ghe.example.com".into(), ProviderConfig {
+            client_id: Some("ghe-client".into()),
+            ..Default::default()
+        });
+        let ghes = Endpoints::for_host(&config, "ghe.example.com").unwrap();
+        assert_eq!(ghes.web_url, "https://ghe.example.com");
+        assert_eq!(ghes.api_url, "https://ghe.example.com/api/v3");
+        assert_eq!(ghes.client_id, "ghe-client");
+
+        config.providers.insert("github.com".into(), ProviderConfig {
+            web_url: Some("http://127.0.0.1:8080/".into()),
+            api_url: Some("http://127.0.0.1:8080/api/".into()),
+            ..Default::default()
+        });
+        let mock = Endpoints::for_host(&config, "github.com").unwrap();
+        assert_eq!(mock.user_url(), "http://127.0.0.1:8080/api/user");
+        assert_eq!(mock.client_id, CLIENT_ID);
+    }
+
+    #[test]
+    fn test_start_device_flow_uses_configured_endpoint() {
+        let (endpoints, handle) = mock_server(vec![(200, vec![], r#"{"device_code":"dc","user_code":"ABCD-1234","verification_uri":"https://ghe.example.com/login/device","expires_in":900,"interval":5}"#)]);
+        let device = start_device_flow(&endpoints, "repo workflow").unwrap();
+        assert_eq!((device.device_code.as_str(), device.user_code.as_str(), device.interval), ("dc", "ABCD-1234", 5));
+        let received = handle.join().unwrap();
+        assert_eq!(received[0].path, "/login/device/code");
+        assert!(received[0].body.contains("client_id=test-client"), "{}", received[0].body);
+        assert!(received[0].body.contains("scope=repo+workflow"), "{}", received[0].body);
+    }
+
+    #[test]
+    fn test_validate_reports_scopes_and_rejection() {
+        let (endpoints, handle) = mock_server(vec![
+            (200, vec![("X-OAuth-Scopes", "repo, read:user")], r#"{"login":"octocat","id":1,"name":null,"email":null}"#),
+            (401, vec![], r#"{"message":"Bad credentials"}"#),
+            (200, vec![], r#"{"login":"octocat","id":1,"name":"The Octocat","email":null}"#),
+            (401, vec![], r#"{"message":"Bad credentials"}"#),
+        ]);
+        let github = GitHub::new(endpoints, &ProviderConfig::default());
+        let info = github.validate(None, "token").unwrap().unwrap();
+        assert_eq!(info.identity.login, "octocat");
+        assert_eq!(info.scopes.unwrap(), ["repo", "read:user"]);
+        assert!(github.validate(None, "bad").unwrap().is_none());
+        // identity も同じ /user の取得を使う
+        assert_eq!(github.identity(None, "token").unwrap().name.as_deref(), Some("The Octocat"));
+        assert!(github.identity(None, "bad").unwrap_err().to_string().contains("rejected"));
+        let received = handle.join().unwrap();
+        assert!(received.iter().all(|r| r.path == "/api/v3/user"));
+        assert_eq!(received[2].authorization.as_deref(), Some("token token"));
+    }
+
+    #[test]
+    fn test_refresh_access_token_returns_rotated_pair() {
+        let (endpoints, handle) = mock_server(vec![(200, vec![], r#"{"access_token":"ghu_new","expires_in":28800,"refresh_token":"ghr_new","refresh_token_expires_in":15897600,"token_type":"bearer"}"#)]);
+        let token = refresh_access_token(&endpoints, "ghr_old").unwrap();
+        assert_eq!(token, AccessToken {
+            access_token: "ghu_new".into(),
+            expires_in: Some(28800),
+            refresh_token: Some("ghr_new".into()),
+            refresh_token_expires_in: Some(15897600),
+        });
+        let received = handle.join().unwrap();
+        assert_eq!(received[0].path, "/login/oauth/access_token");
+        let form = &received[0].body;
+        assert!(form.contains("grant_type=refresh_token"), "{}", form);
+        assert!(form.contains("refresh_token=ghr_old"), "{}", form);
+        assert!(form.contains("client_id=test-client"), "{}", form);
+        assert!(form.contains("client_secret=test-secret"), "{}", form);
+    }
+
+    #[test]
+    fn test_refresh_access_token_reports_error() {
+        let (endpoints, handle) = mock_server(vec![(200, vec![], r#"{"error":"bad_refresh_token","error_description":"The refresh token passed is incorrect or expired."}"#)]);
+        let err = refresh_access_token(&endpoints, "ghr_old").unwrap_err().to_string();
+        assert!(err.contains("bad_refresh_token"), "{}", err);
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn test_revoke_token() {
+        let (endpoints, handle) = mock_server(vec![
+            (204, vec![], ""),
+            (404, vec![], r#"{"message":"Not Found"}"#),
+            (422, vec![], r#"{"message":"Validation Failed"}"#),
+        ]);
+        revoke_token(&endpoints, "gho_token").unwrap();
+        // 失効済みのトークンはエラーにしない（アカウントを削除できるように）
+        revoke_token(&endpoints, "gho_revoked").unwrap();
+        assert!(revoke_token(&endpoints, "gho_token").unwrap_err().to_string().contains("422"));
+        let received = handle.join().unwrap();
+        assert_eq!(received[0].method, "DELETE");
+        assert_eq!(received[0].path, "/api/v3/applications/test-client/token");
+        assert!(received[0].body.contains("gho_token"));
+
+        let without_secret = Endpoints { client_secret: None, ..Endpoints::github() };
+        assert!(revoke_token(&without_secret, "gho_token").unwrap_err().to_string().contains("client_secret"));
+
+        // シークレットがなければ失効を試みず、設定画面を案内する
+        let github = GitHub::new(without_secret, &ProviderConfig::default());
+        assert!(!github.can_revoke());
+        assert_eq!(github.revocation_url(), "https://github.com/settings/applications");
+        assert!(GitHub::new(endpoints, &ProviderConfig::default()).can_revoke());
+    }
 }
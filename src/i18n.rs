@@ -16,6 +16,8 @@ pub enum Key {
     SelectAuthMethod,
     /// ブラウザ認証の選択肢
     AuthMethodBrowser,
+    /// 認可コード + PKCE によるブラウザ認証の選択肢
+    AuthMethodBrowserPkce,
     /// 手動トークン入力の選択肢
     AuthMethodToken,
     /// デバイスコード情報の表示
@@ -50,6 +52,184 @@ pub enum Key {
     AccountRemoved,
     /// 削除対象カウントの選択プロンプト
     SelectAccountToRemove,
+    /// credential.useHttpPath を有効にするかの確認プロンプト
+    AskUseHttpPath,
+    /// credential.useHttpPath 有効化完了時のメッセージ
+    UseHttpPathEnabled,
+    /// コミット用の名前の入力プロンプト
+    EnterCommitName,
+    /// コミット用のメールアドレスの入力プロンプト
+    EnterCommitEmail,
+    /// アカウントに設定したコミット用の名前とメールアドレスの表示
+    CommitIdentity,
+    /// リポジトリのローカル設定へのアカウント設定の書き込み完了時のメッセージ
+    RepoConfigApplied,
+    /// 管理対象の gitconfig の更新に失敗した場合の警告
+    GitConfigSyncFailed,
+    /// SSH 鍵の設定完了時のメッセージ
+    SshKeySet,
+    /// SSH 公開鍵の登録を促すメッセージ
+    SshPublicKeyHint,
+    /// SSH 鍵が未設定の場合のメッセージ
+    SshKeyNotSet,
+    /// コミット署名の設定を表示するメッセージ
+    SigningSet,
+    /// コミット署名を無効にした際のメッセージ
+    SigningDisabled,
+    /// コミット署名が未設定の場合のメッセージ
+    SigningNotSet,
+    /// 診断対象がない場合のメッセージ
+    DoctorNothingToCheck,
+    /// 診断で問題が見つからなかった場合のメッセージ
+    DoctorAllOk,
+    /// 診断で問題が見つかった場合のメッセージ
+    DoctorProblemsFound,
+    /// 暗号化ファイルの再暗号化完了時のメッセージ
+    StoreRekeyed,
+    /// 移行元と移行先が同じ場合のエラー
+    MigrateSameBackend,
+    /// 移行結果の集計
+    MigrateSummary,
+    /// 保存先を切り替えた際のメッセージ
+    BackendSwitched,
+    /// 秘密情報の保存完了時のメッセージ
+    SecretStored,
+    /// 秘密情報の削除完了時のメッセージ
+    SecretDeleted,
+    /// トークンの有効期限の入力を求めるメッセージ
+    EnterTokenExpiry,
+    /// トークンの期限切れが近い場合の警告
+    TokenExpiresSoon,
+    /// トークンが期限切れの場合の警告
+    TokenExpired,
+    /// アプリパスワードの作成を案内するメッセージ
+    AppPasswordInfo,
+    /// アプリパスワード入力プロンプト
+    EnterAppPassword,
+    /// ブラウザを開けない場合に URL を案内するメッセージ
+    OpenUrlManually,
+    /// 認可コードによるブラウザ認証に対応していない場合のエラー
+    AuthCodeUnsupported,
+    /// 検証するアカウントの選択プロンプト
+    SelectAccountToVerify,
+    /// すべてのトークンが有効だった場合のメッセージ
+    VerifyAllOk,
+    /// 問題のあるトークンが見つかった場合のメッセージ（件数）
+    VerifyProblemsFound,
+    /// OAuth トークンを失効させた場合のメッセージ
+    TokenRevoked,
+    /// OAuth トークンを失効させられず、アカウントの削除を中止した場合のエラー
+    TokenRevokeFailed,
+    /// プロバイダーが API での失効に対応していない場合の警告
+    TokenRevokeUnsupported,
+    /// GitHub のトークンを失効させるにはクライアントシークレットが必要であることの案内
+    TokenRevokeNeedsClientSecret,
+    /// 手動でトークンを取り消す設定画面の案内
+    TokenRevokePage,
+    /// `gas explain` の要求の表示
+    ExplainRequest,
+    /// `gas explain` の判定に使用するディレクトリの表示
+    ExplainDirectory,
+    /// 上書き指定が適用された場合の表示
+    ExplainOverrideApplied,
+    /// 上書き指定がない場合の表示
+    ExplainOverrideNotSet,
+    /// 所有者ルールの見出し
+    ExplainOwnerRules,
+    /// パスルールの見出し
+    ExplainPathRules,
+    /// ルールが1件もない場合の表示
+    ExplainNoRules,
+    /// 一致したルールの行
+    ExplainRuleMatched,
+    /// 一致しなかったルールの行
+    ExplainRuleNotMatched,
+    /// 構文が不正なルールの行
+    ExplainRuleInvalid,
+    /// 採用されたルールの印
+    ExplainSelected,
+    /// 上書き指定によって決定した場合の根拠
+    ExplainByOverride,
+    /// 所有者ルールによって決定した場合の根拠
+    ExplainByOwnerRule,
+    /// パスルールによって決定した場合の根拠
+    ExplainByPathRule,
+    /// デフォルトのアカウントによって決定した場合の根拠
+    ExplainByDefault,
+    /// 根拠がない場合の表示
+    ExplainByNothing,
+    /// アカウントが決定した場合の結果
+    ExplainResultUse,
+    /// アカウントが決まらなかった場合の結果
+    ExplainResultNoAccount,
+    /// 決定したアカウントが登録されていない場合の結果
+    ExplainResultUnknownAccount,
+    /// 決定したアカウントがホストを許可していない場合の結果
+    ExplainResultHostNotAllowed,
+    /// 決定したアカウントのユーザー名の表示
+    ExplainUsername,
+    /// 決定したアカウントが無効とマークされている場合の注意
+    ExplainMarkedInvalid,
+    /// トークンの有効期限の表示
+    TokenExpiresOn,
+    /// OAuth トークンの更新に失敗した場合の警告
+    TokenRefreshFailed,
+    /// `gas verify` で有効だったトークンの行（ラベル）
+    VerifyValid,
+    /// `gas verify` で有効だったトークンの行（ラベル・スコープ）
+    VerifyValidScopes,
+    /// `gas verify` で別のユーザーのトークンだった行（ラベル・ログイン名・ユーザー名）
+    VerifyWrongUser,
+    /// `gas verify` でスコープが要求と異なる行（ラベル）
+    VerifyScopeDrift,
+    /// 不足しているスコープの表示
+    VerifyScopesMissing,
+    /// 余分なスコープの表示
+    VerifyScopesExtra,
+    /// `gas verify` でプロバイダーが拒否したトークンの行（ラベル）
+    VerifyInvalid,
+    /// `gas verify` で期限切れだったトークンの行（ラベル・日付）
+    VerifyExpired,
+    /// `gas verify` で秘密情報がなかったアカウントの行（ラベル）
+    VerifyMissing,
+    /// `gas verify` で検証できなかった行（ラベル・理由）
+    VerifyError,
+    /// 移行結果: コピーした
+    MigrateCopied,
+    /// 移行結果: 移動した
+    MigrateMoved,
+    /// 移行結果: 移行済み
+    MigrateAlreadyMigrated,
+    /// 移行結果: 秘密情報がないため省略
+    MigrateSkipped,
+    /// 移行結果: 失敗（理由）
+    MigrateFailed,
+    /// 移行元から読み取れなかった理由（エラー）
+    MigrateReadSourceFailed,
+    /// 移行先から読み取れなかった理由（エラー）
+    MigrateReadDestinationFailed,
+    /// 移行先に書き込めなかった理由（エラー）
+    MigrateWriteFailed,
+    /// 移行先から読み戻せなかった理由（エラー）
+    MigrateReadBackFailed,
+    /// 移行先から読み戻した値が一致しなかった理由
+    MigrateReadBackMismatch,
+    /// 移行元から削除できなかった理由（エラー）
+    MigrateDeleteSourceFailed,
+    /// `gas doctor` で問題がなかった項目の行（アカウント・項目）
+    DoctorItemOk,
+    /// `gas doctor` で問題があった項目の行（アカウント・項目・理由）
+    DoctorItemProblem,
+    /// `gas doctor` の SSH 鍵の項目（パス）
+    DoctorItemSsh,
+    /// `gas doctor` の署名用の鍵の項目（形式・鍵）
+    DoctorItemSigning,
+    /// `gas doctor` でファイルがなかった理由（パス）
+    DoctorFileNotFound,
+    /// `gas doctor` で秘密鍵がなかった理由（コマンド）
+    DoctorSecretKeyNotFound,
+    /// `gas doctor` でコマンドを実行できなかった理由（コマンド・エラー）
+    DoctorCommandFailed,
 }
 
 /// 指定された言語とキーに対応する翻訳済みテキストを返します。
@@ -67,8 +247,9 @@ pub fn t(lang: &Language, key: Key) -> &'static str {
             Key::SelectAccount => "Select account to use in '{}'",
             Key::SelectAuthMethod => "Select authentication method",
             Key::AuthMethodBrowser => "Browser (Recommended)",
+            Key::AuthMethodBrowserPkce => "Browser (authorization code with PKCE, for hosts without device flow)",
             Key::AuthMethodToken => "Manual Input (Personal Access Token)",
-            Key::DeviceCodeInfo => "Copy this code: [{}] -> Press Enter to open GitHub...",
+            Key::DeviceCodeInfo => "Copy this code: [{}] -> Press Enter to open the browser...",
             Key::WaitingForAuth => "Waiting for authorization in browser...",
             Key::AuthSuccess => "Authorization successful! Username: {}",
             Key::AuthFailed => "Authorization failed or timed out.",
@@ -84,6 +265,95 @@ pub fn t(lang: &Language, key: Key) -> &'static str {
             Key::OverrideActive => "Override active: using account '{}'",
             Key::AccountRemoved => "Account '{}' removed successfully.",
             Key::SelectAccountToRemove => "Select account to remove",
+            Key::AskUseHttpPath => "Enable credential.useHttpPath for '{}' so that owner rules can be applied?",
+            Key::UseHttpPathEnabled => "Enabled credential.useHttpPath for '{}'.",
+            Key::EnterCommitName => "Enter name for commits (optional)",
+            Key::EnterCommitEmail => "Enter email for commits (optional)",
+            Key::CommitIdentity => "Commit identity: {} <{}>",
+            Key::RepoConfigApplied => "Applied the settings of account '{}' to this repository's local Git config.",
+            Key::GitConfigSyncFailed => "Warning: failed to update the managed gitconfig: {}",
+            Key::SshKeySet => "Account '{}' now uses SSH key '{}'.",
+            Key::SshPublicKeyHint => "Register the public key '{}' with your Git hosting service. SSH host alias: {}",
+            Key::SshKeyNotSet => "Account '{}' has no SSH key. Use --generate or --key <PATH>.",
+            Key::SigningSet => "Account '{}' signs commits with {}.",
+            Key::SigningDisabled => "Commit signing disabled for account '{}'.",
+            Key::SigningNotSet => "Account '{}' does not sign commits. Use --format <FORMAT> --key <KEY>.",
+            Key::DoctorNothingToCheck => "No SSH or signing keys are configured.",
+            Key::DoctorAllOk => "All configured keys were found.",
+            Key::DoctorProblemsFound => "{} problem(s) found.",
+            Key::StoreRekeyed => "Re-encrypted '{}' ({} secret(s)) with the new key.",
+            Key::MigrateSameBackend => "--from and --to must be different backends.",
+            Key::MigrateSummary => "{} account(s) migrated, {} skipped (no secret stored), {} failed. Run the command again to retry failed accounts.",
+            Key::BackendSwitched => "credential_backend is now '{}'.",
+            Key::SecretStored => "Stored the {} secret of account '{}'.",
+            Key::SecretDeleted => "Deleted the {} secret of account '{}'.",
+            Key::EnterTokenExpiry => "Token expiry date (YYYY-MM-DD, leave empty if it does not expire)",
+            Key::TokenExpiresSoon => "Warning: {} token expires on {} ({} day(s) left).",
+            Key::TokenExpired => "Warning: {} token expired on {} and will not be used.",
+            Key::AppPasswordInfo => "{} does not support browser sign-in. Create an app password (access token) at {} -> Press Enter to open it...",
+            Key::EnterAppPassword => "Enter app password (hidden)",
+            Key::OpenUrlManually => "If the browser does not open, visit: {}",
+            Key::SelectAccountToVerify => "Select account to verify",
+            Key::VerifyAllOk => "All stored tokens are valid.",
+            Key::VerifyProblemsFound => "{} token(s) failed verification.",
+            Key::TokenRevoked => "Revoked the OAuth token of '{}' at the provider.",
+            Key::TokenRevokeFailed => "Error: could not revoke the OAuth token of '{}' at the provider: {}\nThe account was not removed. Try again, or revoke the token manually and remove the account with --keep-token.",
+            Key::TokenRevokeUnsupported => "Warning: gas cannot revoke the OAuth token of '{}' through the {} API. The token is still valid; revoke it manually at: {}",
+            Key::TokenRevokeNeedsClientSecret => "To let gas revoke GitHub tokens, set client_secret of the OAuth App in [providers] of config.toml.",
+            Key::TokenRevokePage => "Revoke it manually at: {}",
+            Key::AuthCodeUnsupported => "{} does not support browser sign-in with an authorization code here. Set client_id (and client_secret for GitHub) in [providers] of config.toml, or choose another method.",
+            Key::ExplainRequest => "Request: {}",
+            Key::ExplainDirectory => "Directory: {}",
+            Key::ExplainOverrideApplied => "{}: {} (applied)",
+            Key::ExplainOverrideNotSet => "{}: not set",
+            Key::ExplainOwnerRules => "Owner rules:",
+            Key::ExplainPathRules => "Path rules:",
+            Key::ExplainNoRules => "(none)",
+            Key::ExplainRuleMatched => "[match]    {} -> {}",
+            Key::ExplainRuleNotMatched => "[no match] {} -> {}",
+            Key::ExplainRuleInvalid => "[invalid]  {} -> {} ({})",
+            Key::ExplainSelected => "  <= selected",
+            Key::ExplainByOverride => "override",
+            Key::ExplainByOwnerRule => "owner rule '{}'",
+            Key::ExplainByPathRule => "path rule '{}'",
+            Key::ExplainByDefault => "default account",
+            Key::ExplainByNothing => "nothing",
+            Key::ExplainResultUse => "Result: account '{}' (selected by {})",
+            Key::ExplainResultNoAccount => "Result: no account (no rule matched and no default account)",
+            Key::ExplainResultUnknownAccount => "Result: none; account '{}' (selected by {}) is not registered",
+            Key::ExplainResultHostNotAllowed => "Result: none; account '{}' (selected by {}) is not allowed for this host",
+            Key::ExplainUsername => "Username: {}",
+            Key::ExplainMarkedInvalid => "Note: '{}' is marked invalid, so no credentials would be returned.",
+            Key::TokenExpiresOn => "{} token expires on {}.",
+            Key::TokenRefreshFailed => "gas: failed to refresh the OAuth token: {}",
+            Key::VerifyValid => "[ok] {}: valid",
+            Key::VerifyValidScopes => "[ok] {}: valid, scopes: {}",
+            Key::VerifyWrongUser => "[!!] {}: token belongs to '{}', not '{}'",
+            Key::VerifyScopeDrift => "[!!] {}: valid, but scopes differ from those requested",
+            Key::VerifyScopesMissing => "missing: {}",
+            Key::VerifyScopesExtra => "extra: {}",
+            Key::VerifyInvalid => "[!!] {}: invalid (rejected by the provider)",
+            Key::VerifyExpired => "[!!] {}: expired on {}",
+            Key::VerifyMissing => "[!!] {}: no secret stored",
+            Key::VerifyError => "[!!] {}: could not verify - {}",
+            Key::MigrateCopied => "copied",
+            Key::MigrateMoved => "moved",
+            Key::MigrateAlreadyMigrated => "already migrated",
+            Key::MigrateSkipped => "skipped (no secret stored)",
+            Key::MigrateFailed => "failed: {}",
+            Key::MigrateReadSourceFailed => "could not read from source: {}",
+            Key::MigrateReadDestinationFailed => "could not read from destination: {}",
+            Key::MigrateWriteFailed => "could not write to destination: {}",
+            Key::MigrateReadBackFailed => "could not read back from destination: {}",
+            Key::MigrateReadBackMismatch => "read-back from destination did not match",
+            Key::MigrateDeleteSourceFailed => "copied, but could not delete from source: {}",
+            Key::DoctorItemOk => "[ok] {}: {}",
+            Key::DoctorItemProblem => "[!!] {}: {} - {}",
+            Key::DoctorItemSsh => "ssh key {}",
+            Key::DoctorItemSigning => "signing key ({}) {}",
+            Key::DoctorFileNotFound => "file not found: {}",
+            Key::DoctorSecretKeyNotFound => "secret key not found by {}",
+            Key::DoctorCommandFailed => "failed to execute {}: {}",
         },
         Language::Ja => match key {
             Key::AskLanguage => "Select Language / 言語を選択してください",
@@ -93,8 +363,9 @@ pub fn t(lang: &Language, key: Key) -> &'static str {
             Key::SelectAccount => "'{}' で使用するアカウントを選択してください",
             Key::SelectAuthMethod => "認証方法を選択してください",
             Key::AuthMethodBrowser => "ブラウザ認証 (推奨)",
+            Key::AuthMethodBrowserPkce => "ブラウザ認証 (認可コード + PKCE、デバイスフロー非対応のホスト向け)",
             Key::AuthMethodToken => "手動入力 (パーソナルアクセストークン)",
-            Key::DeviceCodeInfo => "このコードをコピーしてください: [{}] -> Enterを押すとGitHubを開きます...",
+            Key::DeviceCodeInfo => "このコードをコピーしてください: [{}] -> Enterを押すとブラウザを開きます...",
             Key::WaitingForAuth => "ブラウザでの承認を待機しています...",
             Key::AuthSuccess => "認証に成功しました！ ユーザー名: {}",
             Key::AuthFailed => "認証に失敗したか、タイムアウトしました。",
@@ -110,6 +381,95 @@ pub fn t(lang: &Language, key: Key) -> &'static str {
             Key::OverrideActive => "一時的な切り替え: アカウント '{}' を使用します",
             Key::AccountRemoved => "アカウント '{}' を削除しました。",
             Key::SelectAccountToRemove => "削除するアカウントを選択してください",
+            Key::AskUseHttpPath => "所有者ルールを適用するため、'{}' で credential.useHttpPath を有効にしますか？",
+            Key::UseHttpPathEnabled => "'{}' で credential.useHttpPath を有効にしました。",
+            Key::EnterCommitName => "コミットに使用する名前を入力してください (省略可)",
+            Key::EnterCommitEmail => "コミットに使用するメールアドレスを入力してください (省略可)",
+            Key::CommitIdentity => "コミット用の情報: {} <{}>",
+            Key::RepoConfigApplied => "アカウント '{}' の設定をこのリポジトリのローカルGit設定に反映しました。",
+            Key::GitConfigSyncFailed => "警告: 管理対象の gitconfig の更新に失敗しました: {}",
+            Key::SshKeySet => "アカウント '{}' で SSH 鍵 '{}' を使用します。",
+            Key::SshPublicKeyHint => "公開鍵 '{}' をGitホスティングサービスに登録してください。SSHホストエイリアス: {}",
+            Key::SshKeyNotSet => "アカウント '{}' には SSH 鍵が設定されていません。--generate または --key <パス> を指定してください。",
+            Key::SigningSet => "アカウント '{}' は {} でコミットに署名します。",
+            Key::SigningDisabled => "アカウント '{}' のコミット署名を無効にしました。",
+            Key::SigningNotSet => "アカウント '{}' はコミットに署名しません。--format <形式> --key <鍵> を指定してください。",
+            Key::DoctorNothingToCheck => "SSH 鍵・署名用の鍵は設定されていません。",
+            Key::DoctorAllOk => "設定されたすべての鍵が見つかりました。",
+            Key::DoctorProblemsFound => "{} 件の問題が見つかりました。",
+            Key::StoreRekeyed => "'{}' を新しい鍵で暗号化し直しました（{} 件）。",
+            Key::MigrateSameBackend => "--from と --to には異なる保存先を指定してください。",
+            Key::MigrateSummary => "{} 件のアカウントを移行しました（秘密情報がないため省略: {} 件、失敗: {} 件）。失敗したアカウントは再実行で移行できます。",
+            Key::BackendSwitched => "credential_backend を '{}' に変更しました。",
+            Key::SecretStored => "{} の秘密情報をアカウント '{}' に保存しました。",
+            Key::SecretDeleted => "{} の秘密情報をアカウント '{}' から削除しました。",
+            Key::EnterTokenExpiry => "トークンの有効期限 (YYYY-MM-DD、期限がない場合は空欄)",
+            Key::TokenExpiresSoon => "警告: {} トークンは {} に期限切れになります（残り {} 日）。",
+            Key::TokenExpired => "警告: {} トークンは {} に期限切れになったため使用されません。",
+            Key::AppPasswordInfo => "{} はブラウザでのサインインに対応していません。{} でアプリパスワード（アクセストークン）を作成してください -> Enterを押すと開きます...",
+            Key::EnterAppPassword => "アプリパスワードを入力してください (入力文字は隠れます)",
+            Key::OpenUrlManually => "ブラウザが開かない場合は、次のURLにアクセスしてください: {}",
+            Key::SelectAccountToVerify => "検証するアカウントを選択してください",
+            Key::VerifyAllOk => "保存されているトークンはすべて有効です。",
+            Key::VerifyProblemsFound => "{} 件のトークンで問題が見つかりました。",
+            Key::TokenRevoked => "'{}' の OAuth トークンをプロバイダー側で失効させました。",
+            Key::TokenRevokeFailed => "エラー: '{}' の OAuth トークンをプロバイダー側で失効させられませんでした: {}\nアカウントは削除していません。再度実行するか、トークンを手動で失効させてから --keep-token を指定して削除してください。",
+            Key::TokenRevokeUnsupported => "警告: '{}' の OAuth トークンは {} の API では失効させられません。トークンはまだ有効です。次のページから手動で失効させてください: {}",
+            Key::TokenRevokeNeedsClientSecret => "gas から GitHub のトークンを失効させるには、config.toml の [providers] に OAuth App の client_secret を設定してください。",
+            Key::TokenRevokePage => "次のページから手動で失効させてください: {}",
+            Key::AuthCodeUnsupported => "{} ではこの方法でのブラウザ認証を利用できません。config.toml の [providers] に client_id（GitHub では client_secret も）を設定するか、別の方法を選択してください。",
+            Key::ExplainRequest => "要求: {}",
+            Key::ExplainDirectory => "ディレクトリ: {}",
+            Key::ExplainOverrideApplied => "{}: {}（適用）",
+            Key::ExplainOverrideNotSet => "{}: 未設定",
+            Key::ExplainOwnerRules => "所有者ルール:",
+            Key::ExplainPathRules => "パスルール:",
+            Key::ExplainNoRules => "（なし）",
+            Key::ExplainRuleMatched => "[一致]   {} -> {}",
+            Key::ExplainRuleNotMatched => "[不一致] {} -> {}",
+            Key::ExplainRuleInvalid => "[不正]   {} -> {}（{}）",
+            Key::ExplainSelected => "  <= 採用",
+            Key::ExplainByOverride => "上書き指定",
+            Key::ExplainByOwnerRule => "所有者ルール '{}'",
+            Key::ExplainByPathRule => "パスルール '{}'",
+            Key::ExplainByDefault => "デフォルトのアカウント",
+            Key::ExplainByNothing => "なし",
+            Key::ExplainResultUse => "結果: アカウント '{}'（{} により決定）",
+            Key::ExplainResultNoAccount => "結果: アカウントなし（一致するルールもデフォルトのアカウントもありません）",
+            Key::ExplainResultUnknownAccount => "結果: なし。アカウント '{}'（{} により決定）は登録されていません",
+            Key::ExplainResultHostNotAllowed => "結果: なし。アカウント '{}'（{} により決定）はこのホストを許可していません",
+            Key::ExplainUsername => "ユーザー名: {}",
+            Key::ExplainMarkedInvalid => "注意: '{}' は無効とマークされているため、資格情報は返されません。",
+            Key::TokenExpiresOn => "{} トークンの有効期限は {} です。",
+            Key::TokenRefreshFailed => "gas: OAuth トークンの更新に失敗しました: {}",
+            Key::VerifyValid => "[ok] {}: 有効",
+            Key::VerifyValidScopes => "[ok] {}: 有効、スコープ: {}",
+            Key::VerifyWrongUser => "[!!] {}: '{}' ではなく '{}' のトークンです",
+            Key::VerifyScopeDrift => "[!!] {}: 有効ですが、スコープが要求したものと異なります",
+            Key::VerifyScopesMissing => "不足: {}",
+            Key::VerifyScopesExtra => "余分: {}",
+            Key::VerifyInvalid => "[!!] {}: 無効です（プロバイダーが拒否しました）",
+            Key::VerifyExpired => "[!!] {}: {} に期限切れになりました",
+            Key::VerifyMissing => "[!!] {}: 秘密情報が保存されていません",
+            Key::VerifyError => "[!!] {}: 検証できませんでした - {}",
+            Key::MigrateCopied => "コピーしました",
+            Key::MigrateMoved => "移動しました",
+            Key::MigrateAlreadyMigrated => "移行済みです",
+            Key::MigrateSkipped => "省略しました（秘密情報が保存されていません）",
+            Key::MigrateFailed => "失敗しました: {}",
+            Key::MigrateReadSourceFailed => "移行元から読み取れませんでした: {}",
+            Key::MigrateReadDestinationFailed => "移行先から読み取れませんでした: {}",
+            Key::MigrateWriteFailed => "移行先に書き込めませんでした: {}",
+            Key::MigrateReadBackFailed => "移行先から読み戻せませんでした: {}",
+            Key::MigrateReadBackMismatch => "移行先から読み戻した値が一致しませんでした",
+            Key::MigrateDeleteSourceFailed => "コピーしましたが、移行元から削除できませんでした: {}",
+            Key::DoctorItemOk => "[ok] {}: {}",
+            Key::DoctorItemProblem => "[!!] {}: {} - {}",
+            Key::DoctorItemSsh => "SSH 鍵 {}",
+            Key::DoctorItemSigning => "署名用の鍵 ({}) {}",
+            Key::DoctorFileNotFound => "ファイルが見つかりません: {}",
+            Key::DoctorSecretKeyNotFound => "{} で秘密鍵が見つかりません",
+            Key::DoctorCommandFailed => "{} を実行できませんでした: {}",
         },
     }
 }
@@ -14,18 +14,30 @@
 //! - OS の資格情報マネージャー（Keyring）による安全なパスワード保存。
 //! - 日本語と英語に対応。
 
+mod auth;
 mod config;
 mod credential;
+mod doctor;
 mod ops;
 mod i18n;
+mod migrate;
 mod github_auth;
+mod expiry;
+mod gitconfig;
+mod path_match;
+mod resolve;
+mod ssh;
+mod verify;
 
-use config::{AppConfig, Language};
-use credential::KeyringStore;
+use auth::{AuthProvider, Flow};
+use config::{AccountConfig, AppConfig, BackendKind, Language, SecretMeta, SigningConfig, SigningFormat};
 use clap::{Parser, Subcommand};
-use anyhow::{Context, Result};
-use std::io::{self, Read, BufRead};
+use anyhow::Result;
+use std::io::{self, BufRead};
+use std::path::PathBuf;
 use std::process::{Command, Stdio, exit};
+use credential::SecretKind;
+use credential::encrypted_file::{EncryptedFileStore, KeySource};
 use i18n::{t, Key};
 
 /// Main command-line argument structure / メインのコマンドライン引数構造体
@@ -46,12 +58,21 @@ enum Commands {
     /// Register a new account / アカウントを新しく登録します
     Add { 
         /// アカウントのニックネーム (例: 'Work')
-        name: Option<String> 
+        name: Option<String>,
+        /// Hosts allowed to receive this account's credentials (default: github.com) / 認証情報の提供を許可するホスト（既定: github.com）
+        #[arg(long = "host")]
+        hosts: Vec<String>,
+        /// OAuth scopes to request with browser authentication, comma-separated (default: [providers] in config.toml or the provider's default) / ブラウザ認証で要求する OAuth スコープ（カンマ区切り、既定: config.toml の [providers] またはプロバイダーの既定値）
+        #[arg(long, value_delimiter = ',')]
+        scopes: Vec<String>,
     },
     /// Remove an account / アカウントの削除
     Remove { 
         /// 削除するアカウント名
-        name: Option<String> 
+        name: Option<String>,
+        /// Do not revoke the OAuth token at the provider / プロバイダー側で OAuth トークンを失効させません
+        #[arg(long)]
+        keep_token: bool,
     },
     /// Assign an account to current directory / 現在のディレクトリで使用するアカウントを指定します
     Use { 
@@ -68,16 +89,110 @@ enum Commands {
         #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
         cmd: Vec<String>,
     },
+    /// Show how the account would be resolved / アカウントがどのように決定されるかを表示します
+    Explain {
+        /// Requested host / 要求先のホスト
+        #[arg(long, default_value = "github.com")]
+        host: String,
+        /// Repository path (e.g. org/repo.git) / リポジトリのパス (例: org/repo.git)
+        #[arg(long)]
+        path: Option<String>,
+        /// Directory to resolve for (default: current directory) / 判定に使用するディレクトリ（既定: 現在のディレクトリ）
+        #[arg(long)]
+        dir: Option<PathBuf>,
+        /// Requested protocol / 要求のプロトコル
+        #[arg(long, default_value = "https")]
+        protocol: String,
+    },
+    /// Store or delete a secret of an account / アカウントの秘密情報を保存・削除します
+    Secret {
+        /// 対象のアカウント名
+        account: String,
+        /// Kind of the secret / 秘密情報の種類
+        #[arg(long, value_enum, default_value_t = SecretKind::Pat)]
+        kind: SecretKind,
+        /// Delete the secret instead of storing one / 保存する代わりに削除します
+        #[arg(long, conflicts_with = "expires")]
+        delete: bool,
+        /// Expiry date of the secret (YYYY-MM-DD, UTC) / 秘密情報の有効期限（YYYY-MM-DD、UTC）
+        #[arg(long, value_parser = expiry::parse_date)]
+        expires: Option<u64>,
+    },
+    /// Configure the SSH key of an account / アカウントの SSH 鍵を設定します
+    Ssh {
+        /// 設定するアカウント名
+        account: String,
+        /// Use an existing private key / 既存の秘密鍵を使用します
+        #[arg(long, conflicts_with = "generate")]
+        key: Option<PathBuf>,
+        /// Generate a new ed25519 key / 新しい ed25519 鍵を生成します
+        #[arg(long)]
+        generate: bool,
+    },
+    /// Configure commit signing of an account / アカウントのコミット署名を設定します
+    Signing {
+        /// 設定するアカウント名
+        account: String,
+        /// Signing format / 署名の形式
+        #[arg(long, value_enum, requires = "key", conflicts_with = "disable")]
+        format: Option<SigningFormat>,
+        /// Key ID, fingerprint or SSH key path / 鍵 ID・フィンガープリントまたは SSH 鍵のパス
+        #[arg(long, requires = "format")]
+        key: Option<String>,
+        /// Stop signing commits for this account / このアカウントでのコミット署名を止めます
+        #[arg(long)]
+        disable: bool,
+    },
+    /// Check that configured keys exist locally / 設定された鍵がローカルに存在するか確認します
+    Doctor,
+    /// Check stored tokens against the provider / 保存されたトークンをプロバイダーに問い合わせて検証します
+    Verify {
+        /// 検証するアカウント名
+        #[arg(conflicts_with = "all")]
+        account: Option<String>,
+        /// Verify every account / すべてのアカウントを検証します
+        #[arg(long)]
+        all: bool,
+    },
     /// Change language setting / 言語設定を変更します
     Lang,
     /// [Internal] Git credential helper 'get' command / [内部] Git 認証情報ヘルパーの 'get' コマンド
     Get,
-    /// [Internal] Git credential helper 'store' command / [内部] Git 認証情報ヘルパーの 'store' コマンド
-    Store,
+    /// [Internal] Git credential helper 'store' command, or credential store maintenance / [内部] Git 認証情報ヘルパーの 'store' コマンド、または資格情報ストアの管理
+    Store {
+        #[command(subcommand)]
+        action: Option<StoreAction>,
+    },
     /// [Internal] Git credential helper 'erase' command / [内部] Git 認証情報ヘルパーの 'erase' コマンド
     Erase,
 }
 
+/// Maintenance commands for the credential store / 資格情報ストアの管理コマンド
+#[derive(Subcommand)]
+enum StoreAction {
+    /// Re-encrypt the encrypted credential file with a new passphrase or key file / 暗号化ファイルを新しいパスフレーズまたは鍵ファイルで暗号化し直します
+    Rekey {
+        /// Use this key file instead of a passphrase (created if missing) / パスフレーズの代わりに使用する鍵ファイル（存在しない場合は作成します）
+        #[arg(long)]
+        key_file: Option<PathBuf>,
+    },
+    /// Copy every account's secret to another backend / すべてのアカウントの秘密情報を別の保存先へ移行します
+    Migrate {
+        /// Backend to copy from / 移行元の保存先
+        #[arg(long, value_enum)]
+        from: BackendKind,
+        /// Backend to copy to / 移行先の保存先
+        #[arg(long, value_enum)]
+        to: BackendKind,
+        /// Delete each secret from the source after it was verified / 確認後に移行元から削除します
+        #[arg(long)]
+        delete_source: bool,
+        /// Use the destination as credential_backend when every account succeeded / すべて成功した場合に移行先を credential_backend に設定します
+        #[arg(long)]
+        switch: bool,
+    },
+}
+
 /// Ensure that the application language is set. / アプリケーションの言語が設定されていることを保証します。
 /// If not set, it will ask the user. / 未設定の場合はユーザーに問い合せます。
 fn ensure_language(config: &mut AppConfig) -> Result<Language> {
@@ -90,6 +205,25 @@ fn ensure_language(config: &mut AppConfig) -> Result<Language> {
     Ok(selected_lang)
 }
 
+/// Regenerate the managed gitconfig files and SSH config block; failures are reported but do not abort the command.
+/// 管理対象の gitconfig と SSH 設定のブロックを再生成します。失敗した場合も警告のみでコマンドは継続します。
+fn sync_managed_files(config: &AppConfig, lang: &Language) {
+    if let Err(e) = gitconfig::sync(config).and_then(|_| ssh::sync_ssh_config(config)) {
+        eprintln!("{}", t(lang, Key::GitConfigSyncFailed).replace("{}", &format!("{:#}", e)));
+    }
+}
+
+/// Read the credential description passed by Git on stdin, up to the first blank line.
+/// Git から標準入力で渡される資格情報の記述を、最初の空行まで読み込みます。
+fn read_git_input() -> Result<String> {
+    let mut input = String::new();
+    for line in io::stdin().lock().lines() {
+        let l = line?; if l.trim().is_empty() { break; }
+        input.push_str(&l); input.push('\n');
+    }
+    Ok(input)
+}
+
 fn main() -> Result<()> {
 // (Existing main processing) / (既存の main 処理)
     env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
@@ -101,6 +235,14 @@ fn main() -> Result<()> {
             let lang = ensure_language(&mut config)?;
             ops::setup_git_config()?;
             eprintln!(">>> {}", t(&lang, Key::SetupComplete));
+            for host in ops::owner_rule_hosts(&config) {
+                let prompt = t(&lang, Key::AskUseHttpPath).replace("{}", &host);
+                if dialoguer::Confirm::new().with_prompt(prompt).default(true).interact()? {
+                    ops::enable_use_http_path(&host)?;
+                    eprintln!("{}", t(&lang, Key::UseHttpPathEnabled).replace("{}", &host));
+                }
+            }
+            eprintln!("{}", t(&lang, Key::SetupHint));
         }
         Commands::Lang => {
             let mut config = AppConfig::load()?;
@@ -108,40 +250,119 @@ fn main() -> Result<()> {
             let lang = ensure_language(&mut config)?;
             eprintln!("{}", t(&lang, Key::LanguageChanged));
         }
-        Commands::Add { name } => {
+        Commands::Add { name, hosts, scopes } => {
             let mut config = AppConfig::load()?;
             let lang = ensure_language(&mut config)?;
-            let store = KeyringStore; 
+            let store = credential::open_store(&config)?;
             let account_name = match name {
                 Some(n) => n.clone(),
                 None => dialoguer::Input::new().with_prompt(t(&lang, Key::EnterNickname)).interact_text()?,
             };
-            let auth_methods = vec![t(&lang, Key::AuthMethodBrowser), t(&lang, Key::AuthMethodToken)];
+            let auth_methods = vec![t(&lang, Key::AuthMethodBrowser), t(&lang, Key::AuthMethodBrowserPkce), t(&lang, Key::AuthMethodToken)];
             let selection = dialoguer::Select::new().with_prompt(t(&lang, Key::SelectAuthMethod)).items(&auth_methods).default(0).interact()?;
 
-            let (username, password) = if selection == 0 {
-                let (device_code, user_code, verification_uri, interval) = github_auth::start_device_flow()?;
-                eprintln!("{}", t(&lang, Key::DeviceCodeInfo).replace("{}", &user_code));
-                let _ = dialoguer::Input::<String>::new().allow_empty(true).interact_text()?;
-                let _ = webbrowser::open(&verification_uri);
-                eprintln!("{}", t(&lang, Key::WaitingForAuth));
-                let token = github_auth::poll_for_token(&device_code, interval)?;
-                let user = github_auth::get_username(&token)?;
-                eprintln!("{}", t(&lang, Key::AuthSuccess).replace("{}", &user));
-                (user, token)
+            let (mut account, kind, password, expires_at, oauth_token) = if selection < 2 {
+                let host = hosts.first().map_or("github.com", String::as_str);
+                let requested = Some(scopes.as_slice()).filter(|s| !s.is_empty());
+                let provider = auth::provider_with_scopes(&config, host, requested)?;
+                let (identity, kind, password, token) = if selection == 1 {
+                    let Some(client) = provider.authorization_code() else {
+                        eprintln!("{}", t(&lang, Key::AuthCodeUnsupported).replace("{}", provider.name()));
+                        exit(1);
+                    };
+                    eprintln!("{}", t(&lang, Key::WaitingForAuth));
+                    let authorized = auth::loopback::authorize(&client, |url| {
+                        eprintln!("{}", t(&lang, Key::OpenUrlManually).replace("{}", url));
+                        let _ = webbrowser::open(url);
+                    });
+                    let token = match authorized {
+                        Ok(token) => token,
+                        Err(e) => {
+                            eprintln!("{}", t(&lang, Key::AuthFailed));
+                            return Err(e);
+                        }
+                    };
+                    let identity = provider.identity(None, &token.access_token)?;
+                    (identity, SecretKind::OAuth, token.access_token.clone(), Some(token))
+                } else {
+                    match provider.start()? {
+                        Flow::Device(device) => {
+                            eprintln!("{}", t(&lang, Key::DeviceCodeInfo).replace("{}", &device.user_code));
+                            let _ = dialoguer::Input::<String>::new().allow_empty(true).interact_text()?;
+                            let _ = webbrowser::open(&device.verification_uri);
+                            eprintln!("{}", t(&lang, Key::WaitingForAuth));
+                            let token = match provider.poll(&device) {
+                                Ok(token) => token,
+                                Err(e) => {
+                                    eprintln!("{}", t(&lang, Key::AuthFailed));
+                                    return Err(e);
+                                }
+                            };
+                            let identity = provider.identity(None, &token.access_token)?;
+                            (identity, SecretKind::OAuth, token.access_token.clone(), Some(token))
+                        }
+                        Flow::AppPassword { url } => {
+                            eprintln!("{}", t(&lang, Key::AppPasswordInfo).replacen("{}", provider.name(), 1).replacen("{}", &url, 1));
+                            let _ = dialoguer::Input::<String>::new().allow_empty(true).interact_text()?;
+                            let _ = webbrowser::open(&url);
+                            let u: String = dialoguer::Input::new().with_prompt(t(&lang, Key::EnterUsername)).interact_text()?;
+                            let p = dialoguer::Password::new().with_prompt(t(&lang, Key::EnterAppPassword)).interact()?;
+                            let identity = match provider.identity(Some(&u), &p) {
+                                Ok(identity) => identity,
+                                Err(e) => {
+                                    eprintln!("{}", t(&lang, Key::AuthFailed));
+                                    return Err(e);
+                                }
+                            };
+                            (identity, SecretKind::Pat, p, None)
+                        }
+                    }
+                };
+                eprintln!("{}", t(&lang, Key::AuthSuccess).replace("{}", &identity.login));
+                let account = AccountConfig {
+                    name: Some(identity.name.unwrap_or_else(|| identity.login.clone())),
+                    email: identity.email,
+                    scopes: if kind == SecretKind::OAuth { provider.scopes().to_vec() } else { Vec::new() },
+                    ..AccountConfig::new(identity.login)
+                };
+                (account, kind, password, None, token)
             } else {
-                let u = dialoguer::Input::new().with_prompt(t(&lang, Key::EnterUsername)).interact_text()?;
+                let u: String = dialoguer::Input::new().with_prompt(t(&lang, Key::EnterUsername)).interact_text()?;
                 let p = dialoguer::Password::new().with_prompt(t(&lang, Key::EnterToken)).interact()?;
-                (u, p)
+                let name: String = dialoguer::Input::new().with_prompt(t(&lang, Key::EnterCommitName)).allow_empty(true).interact_text()?;
+                let email: String = dialoguer::Input::new().with_prompt(t(&lang, Key::EnterCommitEmail)).allow_empty(true).interact_text()?;
+                let expires: String = dialoguer::Input::new()
+                    .with_prompt(t(&lang, Key::EnterTokenExpiry))
+                    .allow_empty(true)
+                    .validate_with(|s: &String| if s.is_empty() { Ok(()) } else { expiry::parse_date(s).map(|_| ()).map_err(|e| e.to_string()) })
+                    .interact_text()?;
+                let account = AccountConfig {
+                    name: Some(name).filter(|n| !n.is_empty()),
+                    email: Some(email).filter(|e| !e.is_empty()),
+                    ..AccountConfig::new(u)
+                };
+                let expires_at = if expires.is_empty() { None } else { Some(expiry::parse_date(&expires)?) };
+                (account, SecretKind::Pat, p, expires_at, None)
             };
-            ops::register_account(&mut config, &store, account_name.clone(), username, password)?;
+            if let (Some(name), Some(email)) = (&account.name, &account.email) {
+                eprintln!("{}", t(&lang, Key::CommitIdentity).replacen("{}", name, 1).replacen("{}", email, 1));
+            }
+            account.secrets.insert(kind, SecretMeta { issued_at: Some(expiry::now()), expires_at });
+            let account = if hosts.is_empty() { account } else { AccountConfig { hosts: hosts.clone(), ..account } };
+            ops::register_account(&mut config, &store, account_name.clone(), account, kind, password)?;
+            if let Some(token) = &oauth_token {
+                // 有効期限とリフレッシュトークンを記録する
+                let account = config.accounts.get_mut(&account_name).expect("registered account exists");
+                ops::save_access_token(&store, &account_name, account, token, expiry::now())?;
+            }
             config.save()?;
+            sync_managed_files(&config, &lang);
             eprintln!("{}", t(&lang, Key::AccountRegistered).replacen("{}", &account_name, 1));
         }
-        Commands::Remove { name } => {
+        Commands::Remove { name, keep_token } => {
             let mut config = AppConfig::load()?;
             let lang = ensure_language(&mut config)?;
-            let store = KeyringStore;
+            let store = credential::open_store(&config)?;
             let account_name = match name {
                 Some(n) => n.clone(),
                 None => {
@@ -151,15 +372,46 @@ fn main() -> Result<()> {
                     accounts[selection].clone()
                 }
             };
+            if !*keep_token && let Some(account) = config.accounts.get(&account_name) {
+                let host = account.hosts.first().map_or("github.com", String::as_str);
+                let provider = auth::provider_for_host(&config, host);
+                let provider = match &provider {
+                    Ok(provider) => Ok(provider.as_ref()),
+                    Err(e) => Err(format!("{:#}", e)),
+                };
+                match ops::revoke_oauth_token(&store, &account_name, provider) {
+                    ops::Revocation::NotStored => {}
+                    ops::Revocation::Revoked => eprintln!("{}", t(&lang, Key::TokenRevoked).replace("{}", &account_name)),
+                    ops::Revocation::Manual(provider, url) => {
+                        eprintln!("{}", t(&lang, Key::TokenRevokeUnsupported)
+                            .replacen("{}", &account_name, 1)
+                            .replacen("{}", provider, 1)
+                            .replacen("{}", &url, 1));
+                        // GitHub は client_secret を設定すれば失効させられる（他のプロバイダーは API に対応していない）
+                        if provider == "GitHub" {
+                            eprintln!("{}", t(&lang, Key::TokenRevokeNeedsClientSecret));
+                        }
+                    }
+                    ops::Revocation::Failed(reason, url) => {
+                        eprintln!("{}", t(&lang, Key::TokenRevokeFailed).replacen("{}", &account_name, 1).replacen("{}", &reason, 1));
+                        if let Some(url) = url {
+                            eprintln!("{}", t(&lang, Key::TokenRevokePage).replace("{}", &url));
+                        }
+                        exit(1);
+                    }
+                }
+            }
             ops::remove_account(&mut config, &store, &account_name)?;
             config.save()?;
+            sync_managed_files(&config, &lang);
             eprintln!("{}", t(&lang, Key::AccountRemoved).replace("{}", &account_name));
         }
         Commands::Use { name } => {
             let mut config = AppConfig::load()?;
             let lang = ensure_language(&mut config)?;
             if config.accounts.is_empty() { eprintln!("{}", t(&lang, Key::NoAccounts)); return Ok(()); }
-            let current_dir = std::env::current_dir()?.to_string_lossy().to_string();
+            let cwd = std::env::current_dir()?;
+            let current_dir = path_match::canonicalize(&cwd.to_string_lossy(), &cwd);
             let account_name = match name {
                 Some(n) => n.clone(),
                 None => {
@@ -168,40 +420,296 @@ fn main() -> Result<()> {
                     accounts[selection].clone()
                 }
             };
-            config.path_rules.insert(current_dir.clone(), account_name.clone());
+            config.path_rules.insert(current_dir.clone(), account_name.clone().into());
             config.save()?;
+            sync_managed_files(&config, &lang);
             eprintln!("{}", t(&lang, Key::RuleSaved).replacen("{}", &current_dir, 1).replacen("{}", &account_name, 1));
+            if let Some(account) = config.accounts.get(&account_name)
+                && ops::apply_repo_config(&cwd, account)?
+            {
+                eprintln!("{}", t(&lang, Key::RepoConfigApplied).replace("{}", &account_name));
+            }
         }
         Commands::List => {
             let config = AppConfig::load()?;
+            let lang = config.language.unwrap_or_default();
+            let now = expiry::now();
             eprintln!("--- Accounts ---");
             for (name, details) in &config.accounts {
                 let mark = if config.default_account.as_ref() == Some(name) { " *" } else { "" };
-                eprintln!("{}{}: {}", name, mark, details.username);
+                let invalid = if details.invalid { " (invalid)" } else { "" };
+                let email = details.email.as_ref().map(|e| format!(" <{}>", e)).unwrap_or_default();
+                eprintln!("{}{}: {}{}{}", name, mark, details.username, email, invalid);
+                for (kind, meta) in &details.secrets {
+                    let Some(expires_at) = meta.expires_at else { continue };
+                    let date = expiry::format_date(expires_at);
+                    match expiry::status(Some(expires_at), now) {
+                        expiry::Status::Expired => eprintln!("    {}", t(&lang, Key::TokenExpired).replacen("{}", &kind.to_string(), 1).replacen("{}", &date, 1)),
+                        expiry::Status::ExpiresSoon(days) => eprintln!("    {}", t(&lang, Key::TokenExpiresSoon).replacen("{}", &kind.to_string(), 1).replacen("{}", &date, 1).replacen("{}", &days.to_string(), 1)),
+                        expiry::Status::Valid => eprintln!("    {}", t(&lang, Key::TokenExpiresOn).replacen("{}", &kind.to_string(), 1).replacen("{}", &date, 1)),
+                    }
+                }
+            }
+        }
+        Commands::Explain { host, path, dir, protocol } => {
+            let config = AppConfig::load()?;
+            let lang = config.language.unwrap_or_default();
+            let dir = match dir {
+                Some(d) => d.clone(),
+                None => std::env::current_dir()?,
+            };
+            let ctx = ops::GitContext {
+                protocol: protocol.clone(),
+                host: host.clone(),
+                path: path.clone(),
+                ..Default::default()
+            };
+            let override_acc = std::env::var(ops::ENV_OVERRIDE).ok();
+            let resolution = resolve::resolve(&config, &ctx, &dir.to_string_lossy(), override_acc.as_deref());
+            let request = format!("{}://{}/{}", ctx.protocol, ctx.host, ctx.path.as_deref().unwrap_or(""));
+            eprintln!("{}", t(&lang, Key::ExplainRequest).replace("{}", &request));
+            eprintln!("{}", t(&lang, Key::ExplainDirectory).replace("{}", &dir.display().to_string()));
+            eprintln!("{}", resolution.describe(&lang));
+            if let Some((name, account)) = resolution.target(&config) {
+                eprintln!("{}", t(&lang, Key::ExplainUsername).replace("{}", &account.username));
+                if account.invalid {
+                    eprintln!("{}", t(&lang, Key::ExplainMarkedInvalid).replace("{}", &name));
+                }
+            }
+        }
+        Commands::Secret { account, kind, delete, expires } => {
+            let mut config = AppConfig::load()?;
+            let lang = ensure_language(&mut config)?;
+            if !config.accounts.contains_key(account) {
+                eprintln!("{}", t(&lang, Key::AccountNotFound).replace("{}", account));
+                exit(1);
             }
+            let store = credential::open_store(&config)?;
+            if *delete {
+                store.delete(account, *kind)?;
+                if let Some(account_config) = config.accounts.get_mut(account) {
+                    account_config.secrets.remove(kind);
+                }
+                config.save()?;
+                eprintln!("{}", t(&lang, Key::SecretDeleted).replacen("{}", &kind.to_string(), 1).replacen("{}", account, 1));
+            } else {
+                let secret = dialoguer::Password::new().with_prompt(t(&lang, Key::EnterToken)).interact()?;
+                store.set(account, *kind, &secret)?;
+                // 新しい秘密情報を保存したため無効マークを解除する
+                if let Some(account_config) = config.accounts.get_mut(account) {
+                    account_config.invalid = false;
+                    account_config.secrets.insert(*kind, SecretMeta { issued_at: Some(expiry::now()), expires_at: *expires });
+                }
+                config.save()?;
+                eprintln!("{}", t(&lang, Key::SecretStored).replacen("{}", &kind.to_string(), 1).replacen("{}", account, 1));
+            }
+        }
+        Commands::Ssh { account, key, generate } => {
+            let mut config = AppConfig::load()?;
+            let lang = ensure_language(&mut config)?;
+            let Some(account_config) = config.accounts.get_mut(account) else {
+                eprintln!("{}", t(&lang, Key::AccountNotFound).replace("{}", account));
+                exit(1);
+            };
+            let key_path = if *generate {
+                let path = ssh::default_key_path(account)?;
+                let comment = account_config.email.clone().unwrap_or_else(|| account_config.username.clone());
+                ssh::generate_key(&path, &comment)?;
+                path
+            } else if let Some(k) = key {
+                std::path::absolute(k)?
+            } else {
+                match &account_config.ssh_key {
+                    Some(k) => k.clone(),
+                    None => {
+                        eprintln!("{}", t(&lang, Key::SshKeyNotSet).replace("{}", account));
+                        return Ok(());
+                    }
+                }
+            };
+            account_config.ssh_key = Some(key_path.clone());
+            let host = account_config.hosts.first().cloned().unwrap_or_default();
+            config.save()?;
+            sync_managed_files(&config, &lang);
+            eprintln!("{}", t(&lang, Key::SshKeySet).replacen("{}", account, 1).replacen("{}", &key_path.display().to_string(), 1));
+            let public_key = format!("{}.pub", key_path.display());
+            eprintln!("{}", t(&lang, Key::SshPublicKeyHint).replacen("{}", &public_key, 1).replacen("{}", &ssh::host_alias(&host, account), 1));
+        }
+        Commands::Signing { account, format, key, disable } => {
+            let mut config = AppConfig::load()?;
+            let lang = ensure_language(&mut config)?;
+            let Some(account_config) = config.accounts.get_mut(account) else {
+                eprintln!("{}", t(&lang, Key::AccountNotFound).replace("{}", account));
+                exit(1);
+            };
+            if *disable {
+                account_config.signing = None;
+                eprintln!("{}", t(&lang, Key::SigningDisabled).replace("{}", account));
+            } else if let (Some(format), Some(key)) = (format, key) {
+                account_config.signing = Some(SigningConfig { format: *format, key: key.clone() });
+                eprintln!("{}", t(&lang, Key::SigningSet).replacen("{}", account, 1).replacen("{}", &format!("{} {}", format, key), 1));
+            } else {
+                match &account_config.signing {
+                    Some(signing) => eprintln!("{}", t(&lang, Key::SigningSet).replacen("{}", account, 1).replacen("{}", &format!("{} {}", signing.format, signing.key), 1)),
+                    None => eprintln!("{}", t(&lang, Key::SigningNotSet).replace("{}", account)),
+                }
+                return Ok(());
+            }
+            config.save()?;
+            sync_managed_files(&config, &lang);
+        }
+        Commands::Doctor => {
+            let mut config = AppConfig::load()?;
+            let lang = ensure_language(&mut config)?;
+            let findings = doctor::run(&config);
+            if findings.is_empty() {
+                eprintln!("{}", t(&lang, Key::DoctorNothingToCheck));
+                return Ok(());
+            }
+            for finding in &findings {
+                eprintln!("{}", finding.describe(&lang));
+            }
+            let problems = findings.iter().filter(|f| f.is_problem()).count();
+            if problems > 0 {
+                eprintln!("{}", t(&lang, Key::DoctorProblemsFound).replace("{}", &problems.to_string()));
+                exit(1);
+            }
+            eprintln!("{}", t(&lang, Key::DoctorAllOk));
+        }
+        Commands::Verify { account, all } => {
+            let mut config = AppConfig::load()?;
+            let lang = ensure_language(&mut config)?;
+            if config.accounts.is_empty() { eprintln!("{}", t(&lang, Key::NoAccounts)); return Ok(()); }
+            let names: Vec<String> = match account {
+                Some(name) if !config.accounts.contains_key(name) => {
+                    eprintln!("{}", t(&lang, Key::AccountNotFound).replace("{}", name));
+                    exit(1);
+                }
+                Some(name) => vec![name.clone()],
+                None if *all => config.accounts.keys().cloned().collect(),
+                None => {
+                    let accounts: Vec<String> = config.accounts.keys().cloned().collect();
+                    let selection = dialoguer::Select::new().with_prompt(t(&lang, Key::SelectAccountToVerify)).items(&accounts).interact()?;
+                    vec![accounts[selection].clone()]
+                }
+            };
+            let store = credential::open_store(&config)?;
+            let reports = verify::run(&config, &store, &names, |host| auth::provider_for_host(&config, host));
+            for report in &reports {
+                eprintln!("{}", report.describe(&lang));
+            }
+            let problems = reports.iter().filter(|r| r.is_problem()).count();
+            if problems > 0 {
+                eprintln!("{}", t(&lang, Key::VerifyProblemsFound).replace("{}", &problems.to_string()));
+                exit(1);
+            }
+            eprintln!("{}", t(&lang, Key::VerifyAllOk));
         }
         Commands::With { account, cmd } => {
             let mut config = AppConfig::load()?;
             let lang = ensure_language(&mut config)?;
-            let program = &cmd[0];
-            let mut child = Command::new(program).args(&cmd[1..]).env(ops::ENV_OVERRIDE, account).stdin(Stdio::inherit()).stdout(Stdio::inherit()).stderr(Stdio::inherit()).spawn()?;
+            let Some(account_config) = config.accounts.get(account) else {
+                eprintln!("{}", t(&lang, Key::AccountNotFound).replace("{}", account));
+                exit(1);
+            };
+            let Some(program) = cmd.first() else {
+                eprintln!("{}", t(&lang, Key::NoCommand));
+                exit(1);
+            };
+            eprintln!("{}", t(&lang, Key::OverrideActive).replace("{}", account));
+            let mut child = match Command::new(program).args(&cmd[1..]).env(ops::ENV_OVERRIDE, account).envs(ops::account_env(account_config)).stdin(Stdio::inherit()).stdout(Stdio::inherit()).stderr(Stdio::inherit()).spawn() {
+                Ok(child) => child,
+                Err(e) => {
+                    eprintln!("{}", t(&lang, Key::CommandError).replace("{}", &e.to_string()));
+                    exit(1);
+                }
+            };
             let status = child.wait()?;
             if let Some(code) = status.code() { exit(code); }
         }
         Commands::Get => {
-            let config = AppConfig::load()?;
-            let mut input = String::new();
-            for line in io::stdin().lock().lines() {
-                let l = line?; if l.trim().is_empty() { break; }
-                input.push_str(&l); input.push('\n');
+            let mut config = AppConfig::load()?;
+            let input = read_git_input()?;
+            if !input.is_empty() {
+                let current_dir = std::env::current_dir()?.to_string_lossy().to_string();
+                let override_acc = std::env::var(ops::ENV_OVERRIDE).ok();
+                let store = credential::open_store(&config)?;
+                let provider = auth::provider_for_host(&config, &ops::parse_git_input(&input).host);
+                let refresh = |refresh_token: &str| provider?.refresh(refresh_token);
+                // 更新に失敗しても期限切れとして扱うだけなので、警告を表示して続行する
+                match ops::refresh_expired_token(&mut config, &store, &input, &current_dir, override_acc.clone(), refresh) {
+                    Ok(true) => config.save()?,
+                    Ok(false) => {}
+                    Err(e) => eprintln!("{}", t(&config.language.unwrap_or_default(), Key::TokenRefreshFailed).replace("{}", &format!("{:#}", e))),
+                }
+                if let Some(response) = ops::get_credentials(&config, &store, &input, &current_dir, override_acc)? {
+                    response.write_to(&mut io::stdout().lock())?;
+                }
             }
+        }
+        Commands::Store { action: Some(StoreAction::Rekey { key_file }) } => {
+            let mut config = AppConfig::load()?;
+            let lang = ensure_language(&mut config)?;
+            let store = EncryptedFileStore::from_config(&config)?;
+            let new_source = match key_file {
+                Some(path) => {
+                    let path = std::path::absolute(path)?;
+                    if !path.exists() {
+                        credential::encrypted_file::generate_key_file(&path)?;
+                    }
+                    KeySource::KeyFile(path)
+                }
+                None => KeySource::Prompt,
+            };
+            let count = store.rekey(new_source)?;
+            let settings = config.encrypted_file.get_or_insert_with(Default::default);
+            settings.key_file = key_file.as_ref().map(std::path::absolute).transpose()?;
+            config.save()?;
+            eprintln!("{}", t(&lang, Key::StoreRekeyed).replacen("{}", &store.path().display().to_string(), 1).replacen("{}", &count.to_string(), 1));
+        }
+        Commands::Store { action: Some(StoreAction::Migrate { from, to, delete_source, switch }) } => {
+            let mut config = AppConfig::load()?;
+            let lang = ensure_language(&mut config)?;
+            if from == to {
+                eprintln!("{}", t(&lang, Key::MigrateSameBackend));
+                exit(1);
+            }
+            let source = credential::build_store(*from, &config)?;
+            let destination = credential::build_store(*to, &config)?;
+            let report = migrate::migrate(&config, &source, &destination, *delete_source);
+            for entry in &report {
+                eprintln!("{}: {}", entry.label, entry.outcome.describe(&lang));
+            }
+            let summary = migrate::summarize(&report);
+            eprintln!("{}", t(&lang, Key::MigrateSummary)
+                .replacen("{}", &summary.migrated.to_string(), 1)
+                .replacen("{}", &summary.skipped.to_string(), 1)
+                .replacen("{}", &summary.failed.to_string(), 1));
+            // 読み取れなかった秘密情報があれば、移行元を残したまま終了する（--switch も行わない）
+            if summary.failed > 0 {
+                exit(1);
+            }
+            if *switch {
+                config.credential_backend = *to;
+                config.save()?;
+                eprintln!("{}", t(&lang, Key::BackendSwitched).replace("{}", &to.to_string()));
+            }
+        }
+        Commands::Store { action: None } | Commands::Erase => {
+            let mut config = AppConfig::load()?;
+            let input = read_git_input()?;
             if !input.is_empty() {
                 let current_dir = std::env::current_dir()?.to_string_lossy().to_string();
                 let override_acc = std::env::var(ops::ENV_OVERRIDE).ok();
-                ops::get_credentials(&config, &KeyringStore, &input, &current_dir, override_acc)?;
+                let store = credential::open_store(&config)?;
+                let changed = if matches!(cli.command, Commands::Store { .. }) {
+                    ops::store_credentials(&mut config, &store, &input, &current_dir, override_acc)?
+                } else {
+                    ops::erase_credentials(&mut config, &store, &input, &current_dir, override_acc)?
+                };
+                if changed { config.save()?; }
             }
         }
-        _ => {}
     }
     Ok(())
 }
\ No newline at end of file
@@ -0,0 +1,293 @@
+use crate::config::{AppConfig, Language};
+use crate::credential::{self, CredentialStore, SecretKind};
+use crate::i18n::{t, Key};
+
+/// 1つのアカウントの移行結果
+#[derive(Debug, Clone, PartialEq)]
+pub enum Outcome {
+    /// 移行先へコピーし、読み戻して確認した
+    Copied,
+    /// 移行先へコピー・確認した後、移行元から削除した
+    Moved,
+    /// 移行先にすでに同じ秘密情報があった（以前の実行で移行済み）
+    AlreadyMigrated,
+    /// 移行元にも移行先にも秘密情報がなかった（`erase_policy = "delete"` で削除された後など、正常な状態）
+    Skipped,
+    /// 移行できなかった（理由）
+    Failed(Failure),
+}
+
+/// 移行に失敗した段階（エラーの内容）
+#[derive(Debug, Clone, PartialEq)]
+pub enum Failure {
+    /// 移行元から読み取れなかった
+    ReadSource(String),
+    /// 移行先から読み取れなかった
+    ReadDestination(String),
+    /// 移行先に書き込めなかった
+    Write(String),
+    /// 移行先から読み戻せなかった
+    ReadBack(String),
+    /// 移行先から読み戻した値が一致しなかった
+    Mismatch,
+    /// コピーした後、移行元から削除できなかった
+    DeleteSource(String),
+}
+
+impl Outcome {
+    /// `gas store migrate` 向けに移行結果を表します。
+    pub fn describe(&self, lang: &Language) -> String {
+        let failure = |key, e: &str| t(lang, key).replace("{}", e);
+        match self {
+            Outcome::Copied => t(lang, Key::MigrateCopied).to_string(),
+            Outcome::Moved => t(lang, Key::MigrateMoved).to_string(),
+            Outcome::AlreadyMigrated => t(lang, Key::MigrateAlreadyMigrated).to_string(),
+            Outcome::Skipped => t(lang, Key::MigrateSkipped).to_string(),
+            Outcome::Failed(reason) => {
+                let reason = match reason {
+                    Failure::ReadSource(e) => failure(Key::MigrateReadSourceFailed, e),
+                    Failure::ReadDestination(e) => failure(Key::MigrateReadDestinationFailed, e),
+                    Failure::Write(e) => failure(Key::MigrateWriteFailed, e),
+                    Failure::ReadBack(e) => failure(Key::MigrateReadBackFailed, e),
+                    Failure::Mismatch => t(lang, Key::MigrateReadBackMismatch).to_string(),
+                    Failure::DeleteSource(e) => failure(Key::MigrateDeleteSourceFailed, e),
+                };
+                t(lang, Key::MigrateFailed).replace("{}", &reason)
+            }
+        }
+    }
+}
+
+/// アカウントの秘密情報1種類ごとの移行結果
+#[derive(Debug, Clone, PartialEq)]
+pub struct Report {
+    /// アカウントのニックネーム
+    pub account: String,
+    /// 表示ラベル（PAT ではニックネーム、それ以外の種類では `<nickname> (<kind>)`）
+    pub label: String,
+    /// 移行結果
+    pub outcome: Outcome,
+}
+
+/// アカウント単位で集計した移行結果の件数
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Summary {
+    /// すべての秘密情報を移行できた（移行済みを含む）アカウント
+    pub migrated: usize,
+    /// 秘密情報が1つもなかったアカウント
+    pub skipped: usize,
+    /// 1種類でも移行できなかったアカウント
+    pub failed: usize,
+}
+
+/// 秘密情報を取得します。保存されていない場合は `Ok(None)`、読み取れなかった場合はエラーの内容を返します。
+fn read(store: &impl CredentialStore, nickname: &str, kind: SecretKind) -> Result<Option<String>, String> {
+    match store.get(nickname, kind) {
+        Ok(secret) => Ok(Some(secret)),
+        Err(e) if credential::is_not_found(&e) => Ok(None),
+        Err(e) => Err(format!("{:#}", e)),
+    }
+}
+
+/// 1つのアカウントの1種類の秘密情報を移行します。
+///
+/// 移行先に同じ秘密情報がすでにある場合はコピーを省略するため、途中で中断した移行を再実行できます。
+/// 移行元からの削除（`delete_source`）は、移行先から読み戻した値が一致した場合にのみ行います。
+/// 移行元にも移行先にもない種類については `None` を返します。
+/// 保存されていないこと以外の理由で読み取れなかった場合（Keyring のロック等）は `Failed` を返します。
+fn migrate_secret(
+    from: &impl CredentialStore,
+    to: &impl CredentialStore,
+    nickname: &str,
+    kind: SecretKind,
+    delete_source: bool,
+) -> Option<Outcome> {
+    let existing = match read(to, nickname, kind) {
+        Ok(existing) => existing,
+        Err(e) => return Some(Outcome::Failed(Failure::ReadDestination(e))),
+    };
+    let secret = match read(from, nickname, kind) {
+        Ok(Some(secret)) => secret,
+        // 以前の実行で移行元から削除済み
+        Ok(None) if existing.is_some() => return Some(Outcome::AlreadyMigrated),
+        Ok(None) => return None,
+        Err(e) => return Some(Outcome::Failed(Failure::ReadSource(e))),
+    };
+
+    let copied = existing.as_deref() != Some(secret.as_str());
+    if copied {
+        if let Err(e) = to.set(nickname, kind, &secret) {
+            return Some(Outcome::Failed(Failure::Write(format!("{:#}", e))));
+        }
+        match to.get(nickname, kind) {
+            Ok(read_back) if read_back == secret => {}
+            Ok(_) => return Some(Outcome::Failed(Failure::Mismatch)),
+            Err(e) => return Some(Outcome::Failed(Failure::ReadBack(format!("{:#}", e)))),
+        }
+    }
+
+    if delete_source {
+        if let Err(e) = from.delete(nickname, kind) {
+            return Some(Outcome::Failed(Failure::DeleteSource(format!("{:#}", e))));
+        }
+        return Some(Outcome::Moved);
+    }
+    Some(if copied { Outcome::Copied } else { Outcome::AlreadyMigrated })
+}
+
+/// `config.accounts` に登録されたすべてのアカウントの秘密情報を、種類ごとに `from` から `to` へ移行します。
+///
+/// 1つのアカウントで失敗しても残りのアカウントの移行は続行し、アカウント名順に結果を返します。
+/// 移行元にも移行先にも秘密情報が1つもないアカウントは `Skipped` として報告します（失敗には数えません）。
+pub fn migrate(
+    config: &AppConfig,
+    from: &impl CredentialStore,
+    to: &impl CredentialStore,
+    delete_source: bool,
+) -> Vec<Report> {
+    let mut names: Vec<_> = config.accounts.keys().cloned().collect();
+    names.sort();
+    let mut reports = Vec::new();
+    for name in names {
+        let before = reports.len();
+        for kind in SecretKind::ALL {
+            if let Some(outcome) = migrate_secret(from, to, &name, kind, delete_source) {
+                let label = match kind {
+                    SecretKind::Pat => name.clone(),
+                    kind => format!("{} ({})", name, kind),
+                };
+                reports.push(Report { account: name.clone(), label, outcome });
+            }
+        }
+        if reports.len() == before {
+            reports.push(Report { account: name.clone(), label: name, outcome: Outcome::Skipped });
+        }
+    }
+    reports
+}
+
+/// 移行結果をアカウント単位で集計します。1種類でも失敗したアカウントは失敗として数えます。
+pub fn summarize(reports: &[Report]) -> Summary {
+    let mut summary = Summary::default();
+    // `migrate` は結果をアカウントごとにまとめて返す
+    for account in reports.chunk_by(|a, b| a.account == b.account) {
+        if account.iter().any(|r| matches!(r.outcome, Outcome::Failed(_))) {
+            summary.failed += 1;
+        } else if account.iter().all(|r| r.outcome == Outcome::Skipped) {
+            summary.skipped += 1;
+        } else {
+            summary.migrated += 1;
+        }
+    }
+    summary
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::AccountConfig;
+    use crate::credential::MockStore;
+
+    fn config() -> AppConfig {
+        let mut config = AppConfig::default();
+        for name in ["Home", "Missing", "Work"] {
+            config.accounts.insert(name.into(), AccountConfig::new(name.to_lowercase()));
+        }
+        config
+    }
+
+    #[test]
+    fn test_migrate_copies_and_reports_each_account() {
+        let from = MockStore::new();
+        let to = MockStore::new();
+        from.set("Home", SecretKind::Pat, "home-token").unwrap();
+        from.set("Work", SecretKind::Pat, "work-token").unwrap();
+
+        let report = migrate(&config(), &from, &to, false);
+        let outcomes: Vec<_> = report.iter().map(|r| (r.label.as_str(), &r.outcome)).collect();
+        assert_eq!(outcomes, [("Home", &Outcome::Copied), ("Missing", &Outcome::Skipped), ("Work", &Outcome::Copied)]);
+        assert_eq!(to.get("Work", SecretKind::Pat).unwrap(), "work-token");
+        // 移行元は削除しない
+        assert_eq!(from.get("Work", SecretKind::Pat).unwrap(), "work-token");
+    }
+
+    #[test]
+    fn test_migrate_is_resumable_and_deletes_source_when_asked() {
+        let from = MockStore::new();
+        let to = MockStore::new();
+        from.set("Home", SecretKind::Pat, "home-token").unwrap();
+        from.set("Work", SecretKind::Pat, "work-token").unwrap();
+        // 前回の実行で Home のみ移行済み
+        to.set("Home", SecretKind::Pat, "home-token").unwrap();
+
+        let report = migrate(&config(), &from, &to, false);
+        assert_eq!(report[0].outcome, Outcome::AlreadyMigrated);
+        assert_eq!(report[2].outcome, Outcome::Copied);
+
+        let report = migrate(&config(), &from, &to, true);
+        assert_eq!(report[0].outcome, Outcome::Moved);
+        assert_eq!(report[2].outcome, Outcome::Moved);
+        assert!(from.get("Work", SecretKind::Pat).is_err());
+
+        // 移行元から削除済みでも再実行できる
+        let report = migrate(&config(), &from, &to, true);
+        assert_eq!(report[0].outcome, Outcome::AlreadyMigrated);
+        assert_eq!(to.get("Work", SecretKind::Pat).unwrap(), "work-token");
+    }
+
+    #[test]
+    fn test_migrate_reports_each_secret_kind() {
+        let from = MockStore::new();
+        let to = MockStore::new();
+        from.set("Work", SecretKind::OAuth, "oauth-token").unwrap();
+        from.set("Work", SecretKind::RefreshToken, "refresh-token").unwrap();
+
+        let report = migrate(&config(), &from, &to, false);
+        let labels: Vec<_> = report.iter().map(|r| r.label.as_str()).collect();
+        assert_eq!(labels, ["Home", "Missing", "Work (oauth)", "Work (refresh-token)"]);
+        assert_eq!(to.get("Work", SecretKind::RefreshToken).unwrap(), "refresh-token");
+        // 件数は種類ごとではなくアカウントごとに数える
+        assert_eq!(summarize(&report), Summary { migrated: 1, skipped: 2, failed: 0 });
+    }
+
+    /// 指定したアカウントの読み取りに失敗するストア（ロックされた Keyring 等）
+    struct LockedStore {
+        inner: MockStore,
+        locked: &'static str,
+    }
+
+    impl CredentialStore for LockedStore {
+        fn set(&self, account: &str, kind: SecretKind, secret: &str) -> anyhow::Result<()> {
+            self.inner.set(account, kind, secret)
+        }
+
+        fn get(&self, account: &str, kind: SecretKind) -> anyhow::Result<String> {
+            if account == self.locked {
+                anyhow::bail!("keyring is locked");
+            }
+            self.inner.get(account, kind)
+        }
+
+        fn delete(&self, account: &str, kind: SecretKind) -> anyhow::Result<()> {
+            self.inner.delete(account, kind)
+        }
+    }
+
+    #[test]
+    fn test_migrate_reports_unreadable_source_as_failed() {
+        let from = LockedStore { inner: MockStore::new(), locked: "Missing" };
+        let to = MockStore::new();
+        from.set("Home", SecretKind::Pat, "home-token").unwrap();
+        from.set("Work", SecretKind::Pat, "work-token").unwrap();
+        from.set("Work", SecretKind::OAuth, "oauth-token").unwrap();
+
+        let report = migrate(&config(), &from, &to, false);
+        // 読み取れなかったアカウントを「秘密情報がない」として省略しない
+        let missing: Vec<_> = report.iter().filter(|r| r.account == "Missing").collect();
+        assert!(!missing.is_empty());
+        assert!(missing.iter().all(|r| r.outcome == Outcome::Failed(Failure::ReadSource("keyring is locked".into()))));
+        assert_eq!(missing[0].outcome.describe(&Language::En), "failed: could not read from source: keyring is locked");
+        assert_eq!(missing[0].outcome.describe(&Language::Ja), "失敗しました: 移行元から読み取れませんでした: keyring is locked");
+        assert_eq!(summarize(&report), Summary { migrated: 2, skipped: 0, failed: 1 });
+    }
+}
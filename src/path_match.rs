@@ -0,0 +1,342 @@
+use anyhow::{Context, Result};
+use globset::{GlobBuilder, GlobMatcher};
+use regex::Regex;
+use std::path::{Component, Path, PathBuf};
+
+/// 正規表現ルールを表すキーの接頭辞
+pub const REGEX_PREFIX: &str = "re:";
+
+/// パスの比較方法（区切り文字と大文字・小文字の扱い）を表す構造体
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PathStyle {
+    /// 大文字・小文字を区別するかどうか
+    pub case_sensitive: bool,
+    /// `\` を区切り文字として扱うかどうか
+    pub backslash_separator: bool,
+}
+
+impl PathStyle {
+    /// Windows 形式（`\` と `/` の両方が区切り文字、大文字・小文字を区別しない）
+    pub const WINDOWS: PathStyle = PathStyle { case_sensitive: false, backslash_separator: true };
+    /// POSIX 形式（`/` のみが区切り文字、大文字・小文字を区別する）
+    pub const POSIX: PathStyle = PathStyle { case_sensitive: true, backslash_separator: false };
+    /// macOS 形式（`/` のみが区切り文字、既定のファイルシステムに合わせて大文字・小文字を区別しない）
+    pub const MACOS: PathStyle = PathStyle { case_sensitive: false, backslash_separator: false };
+
+    /// 実行中の OS に対応する比較方法を返します。
+    pub fn native() -> Self {
+        if cfg!(windows) {
+            Self::WINDOWS
+        } else if cfg!(target_os = "macos") {
+            Self::MACOS
+        } else {
+            Self::POSIX
+        }
+    }
+
+    /// パス文字列を区切り文字で分割し、空の要素を除いた構成要素の一覧を返します。
+    fn components<'a>(&self, path: &'a str) -> Vec<&'a str> {
+        path.split(|c| c == '/' || (self.backslash_separator && c == '\\'))
+            .filter(|c| !c.is_empty())
+            .collect()
+    }
+
+    fn component_eq(&self, a: &str, b: &str) -> bool {
+        if self.case_sensitive { a == b } else { a.to_lowercase() == b.to_lowercase() }
+    }
+
+    /// `path` が `prefix` と同じディレクトリ、またはその配下にあるかを判定します。
+    ///
+    /// 文字列の前方一致ではなく構成要素単位で比較するため、`/src/foo` は `/src/foobar` に一致しません。
+    pub fn is_within(&self, path: &str, prefix: &str) -> bool {
+        let path = self.components(path);
+        let prefix = self.components(prefix);
+        prefix.len() <= path.len()
+            && prefix.iter().zip(&path).all(|(a, b)| self.component_eq(a, b))
+    }
+
+    /// パスが絶対パス（Windows 形式ではドライブ文字付きを含む）かどうかを判定します。
+    pub fn is_absolute(&self, path: &str) -> bool {
+        let bytes = path.as_bytes();
+        path.starts_with('/')
+            || (self.backslash_separator
+                && (path.starts_with('\\') || (bytes.len() >= 2 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':')))
+    }
+
+    /// 区切り文字を `/` に統一します。
+    pub fn slashed(&self, path: &str) -> String {
+        if self.backslash_separator { path.replace('\\', "/") } else { path.to_string() }
+    }
+
+    /// パスの構成要素の数を返します。ルールの具体性（深さ）の比較に使用します。
+    pub fn depth(&self, path: &str) -> usize {
+        self.components(path).len()
+    }
+}
+
+/// `path_rules` のキーを解釈したパターン
+///
+/// グロブと正規表現は、区切り文字を `/` に統一した正規化済みのパスに対して照合されます。
+pub enum PathPattern {
+    /// 絶対パス。そのディレクトリと配下のディレクトリに一致します。
+    Literal(String),
+    /// グロブ（`*`, `?`, `[...]`, `{a,b}`, `**`）。一致したディレクトリの配下にも一致します。
+    Glob { source: String, matcher: GlobMatcher },
+    /// `re:` で始まる正規表現。正規化済みのパスに対して検索します（アンカーは利用者が指定します）。
+    Regex(Regex),
+}
+
+impl PathPattern {
+    /// `path_rules` のキーを解釈します。
+    ///
+    /// # Arguments
+    /// * `rule` - ルールのキー
+    /// * `base` - 相対パスの基準となるディレクトリ
+    /// * `style` - パスの比較方法
+    ///
+    /// # Errors
+    /// グロブまたは正規表現の構文が不正な場合にエラーを返します。
+    pub fn parse(rule: &str, base: &Path, style: PathStyle) -> Result<Self> {
+        if let Some(pattern) = rule.strip_prefix(REGEX_PREFIX) {
+            let regex = Regex::new(pattern)
+                .with_context(|| format!("Invalid regex in path rule '{}'", rule))?;
+            return Ok(PathPattern::Regex(regex));
+        }
+        if !rule.contains(['*', '?', '[', '{']) {
+            return Ok(PathPattern::Literal(canonicalize(rule, base)));
+        }
+        let expanded = expand_tilde(rule).to_string_lossy().to_string();
+        let absolute = if style.is_absolute(&expanded) {
+            expanded
+        } else {
+            base.join(expanded).to_string_lossy().to_string()
+        };
+        let source = style.slashed(&absolute);
+        let matcher = GlobBuilder::new(&source)
+            .literal_separator(true)
+            .case_insensitive(!style.case_sensitive)
+            .build()
+            .with_context(|| format!("Invalid glob in path rule '{}'", rule))?
+            .compile_matcher();
+        Ok(PathPattern::Glob { source, matcher })
+    }
+
+    /// 正規化済みのディレクトリ `dir` がこのパターンに一致するかを判定します。
+    pub fn matches(&self, dir: &str, style: PathStyle) -> bool {
+        match self {
+            PathPattern::Literal(prefix) => style.is_within(dir, prefix),
+            PathPattern::Glob { matcher, .. } => {
+                let mut candidate = style.slashed(dir);
+                loop {
+                    if matcher.is_match(&candidate) {
+                        return true;
+                    }
+                    match candidate.rfind('/') {
+                        Some(idx) if idx > 0 => candidate.truncate(idx),
+                        _ => return false,
+                    }
+                }
+            }
+            PathPattern::Regex(regex) => regex.is_match(&style.slashed(dir)),
+        }
+    }
+
+    /// ルールの具体性を返します。値が大きいほど具体的なルールとして優先されます。
+    ///
+    /// 1. ワイルドカード等を含まない先頭の構成要素の数（深さ）
+    /// 2. 同じ深さの場合は パス > グロブ > 正規表現 の順
+    pub fn specificity(&self, style: PathStyle) -> (usize, u8) {
+        match self {
+            PathPattern::Literal(prefix) => (style.depth(prefix), 2),
+            PathPattern::Glob { source, .. } => (literal_depth(source, &['*', '?', '[', '{']), 1),
+            PathPattern::Regex(regex) => {
+                let source = regex.as_str().trim_start_matches('^');
+                (literal_depth(source, &['.', '^', '$', '*', '+', '?', '(', ')', '[', ']', '{', '}', '|', '\\']), 0)
+            }
+        }
+    }
+}
+
+/// `/` 区切りのパターンのうち、`meta` のいずれも含まない先頭の構成要素の数を返します。
+fn literal_depth(pattern: &str, meta: &[char]) -> usize {
+    pattern.split('/')
+        .filter(|c| !c.is_empty())
+        .take_while(|c| !c.contains(meta))
+        .count()
+}
+
+/// `path_rules` の相対パスのキーを解決する基準のディレクトリ（ホームディレクトリ）を返します。
+///
+/// 資格情報の判定（`resolve`）と `includeIf` の生成（`gitconfig`）が同じ基準を使用するよう、両方からこの関数を使用します。
+pub fn rule_base() -> PathBuf {
+    dirs::home_dir().unwrap_or_default()
+}
+
+/// 先頭の `~` をホームディレクトリに展開します。
+pub fn expand_tilde(path: &str) -> PathBuf {
+    let rest = if path == "~" {
+        Some("")
+    } else {
+        path.strip_prefix("~/").or_else(|| path.strip_prefix("~\\"))
+    };
+    match (rest, dirs::home_dir()) {
+        (Some(rest), Some(home)) => home.join(rest),
+        _ => PathBuf::from(path),
+    }
+}
+
+/// パスを比較可能な絶対パスへ正規化します。
+///
+/// `~` の展開、`base` を基準とした相対パスの解決を行い、パスが存在する場合はシンボリックリンクも解決します。
+/// 存在しないパスは `.` と `..` を字句的に取り除いた形で返します。
+///
+/// # Arguments
+/// * `path` - 正規化するパス（設定ファイルに記述されたルール等）
+/// * `base` - 相対パスの基準となるディレクトリ
+pub fn canonicalize(path: &str, base: &Path) -> String {
+    let expanded = expand_tilde(path);
+    let absolute = if expanded.is_absolute() { expanded } else { base.join(expanded) };
+    let resolved = std::fs::canonicalize(&absolute).unwrap_or_else(|_| normalize_lexically(&absolute));
+    strip_verbatim_prefix(&resolved.to_string_lossy()).to_string()
+}
+
+/// ファイルシステムに問い合わせずに `.` と `..` を取り除きます。
+fn normalize_lexically(path: &Path) -> PathBuf {
+    let mut out = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => { out.pop(); }
+            other => out.push(other),
+        }
+    }
+    out
+}
+
+/// Windows の `canonicalize` が付与する `\\?\` 接頭辞を取り除きます。
+fn strip_verbatim_prefix(path: &str) -> &str {
+    path.strip_prefix(r"\\?\").unwrap_or(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_posix_is_case_sensitive() {
+        let style = PathStyle::POSIX;
+        assert!(style.is_within("/home/me/work/repo", "/home/me/work"));
+        assert!(!style.is_within("/home/me/Work/repo", "/home/me/work"));
+    }
+
+    #[test]
+    fn test_posix_matches_on_component_boundary() {
+        let style = PathStyle::POSIX;
+        assert!(style.is_within("/src/foo", "/src/foo"));
+        assert!(style.is_within("/src/foo/bar", "/src/foo/"));
+        assert!(!style.is_within("/src/foobar", "/src/foo"));
+        assert!(!style.is_within("/src", "/src/foo"));
+        // POSIX ではバックスラッシュはファイル名の一部
+        assert!(!style.is_within("/src/foo\\bar", "/src/foo"));
+    }
+
+    #[test]
+    fn test_windows_is_case_insensitive_and_accepts_both_separators() {
+        let style = PathStyle::WINDOWS;
+        assert!(style.is_within(r"C:\Projects\Home\repo", "c:/projects/home"));
+        assert!(style.is_within("C:/projects/home", r"C:\PROJECTS\HOME\"));
+        assert!(!style.is_within(r"C:\projects\homework", r"C:\projects\home"));
+    }
+
+    #[test]
+    fn test_depth() {
+        assert_eq!(PathStyle::POSIX.depth("/a/b/c/"), 3);
+        assert_eq!(PathStyle::WINDOWS.depth(r"C:\a\b"), 3);
+    }
+
+    #[test]
+    fn test_expand_tilde() {
+        let home = dirs::home_dir().unwrap();
+        assert_eq!(expand_tilde("~/src"), home.join("src"));
+        assert_eq!(expand_tilde("~"), home);
+        assert_eq!(expand_tilde("/abs/~"), PathBuf::from("/abs/~"));
+    }
+
+    #[test]
+    fn test_canonicalize_resolves_relative_segments() {
+        let dir = tempdir().unwrap();
+        let base = std::fs::canonicalize(dir.path()).unwrap();
+        std::fs::create_dir(base.join("a")).unwrap();
+
+        assert_eq!(canonicalize("a/./../a", &base), base.join("a").to_string_lossy());
+        // 存在しないパスは字句的に正規化される
+        assert_eq!(canonicalize("missing/../other", &base), base.join("other").to_string_lossy());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_canonicalize_resolves_symlinks() {
+        let dir = tempdir().unwrap();
+        let base = std::fs::canonicalize(dir.path()).unwrap();
+        std::fs::create_dir(base.join("real")).unwrap();
+        std::os::unix::fs::symlink(base.join("real"), base.join("link")).unwrap();
+
+        assert_eq!(canonicalize("link", &base), base.join("real").to_string_lossy());
+    }
+
+    fn parse(rule: &str, style: PathStyle) -> PathPattern {
+        PathPattern::parse(rule, Path::new("/"), style).unwrap()
+    }
+
+    #[test]
+    fn test_glob_pattern_matches_directory_and_descendants() {
+        let style = PathStyle::POSIX;
+        let pattern = parse("/src/work-*/**", style);
+        assert!(pattern.matches("/src/work-acme/repo", style));
+        assert!(pattern.matches("/src/work-acme/repo/sub", style));
+        assert!(!pattern.matches("/src/personal/repo", style));
+
+        let pattern = parse("/src/work-*", style);
+        assert!(pattern.matches("/src/work-acme", style));
+        assert!(pattern.matches("/src/work-acme/repo", style));
+        assert!(!pattern.matches("/src/work/acme", style));
+    }
+
+    #[test]
+    fn test_glob_pattern_expands_tilde() {
+        let style = PathStyle::native();
+        let home = dirs::home_dir().unwrap();
+        let pattern = parse("~/src/work-*", style);
+        assert!(pattern.matches(&home.join("src/work-a/repo").to_string_lossy(), style));
+    }
+
+    #[test]
+    fn test_glob_pattern_windows_style() {
+        let style = PathStyle::WINDOWS;
+        let pattern = PathPattern::parse("C:/Src/Work-*", Path::new("C:/"), style).unwrap();
+        assert!(pattern.matches(r"c:\src\work-acme\repo", style));
+    }
+
+    #[test]
+    fn test_regex_pattern() {
+        let style = PathStyle::POSIX;
+        let pattern = parse("re:^/srv/clients/[^/]+/acme", style);
+        assert!(pattern.matches("/srv/clients/east/acme/repo", style));
+        assert!(!pattern.matches("/srv/clients/east/other", style));
+        assert!(PathPattern::parse("re:(", Path::new("/"), style).is_err());
+    }
+
+    #[test]
+    fn test_specificity_order() {
+        let style = PathStyle::POSIX;
+        let literal = parse("/srv/clients", style).specificity(style);
+        let glob = parse("/srv/clients/*/acme", style).specificity(style);
+        let regex = parse("re:^/srv/clients/[^/]+/acme", style).specificity(style);
+        assert_eq!(literal, (2, 2));
+        assert_eq!(glob, (2, 1));
+        assert_eq!(regex, (2, 0));
+        assert!(literal > glob && glob > regex);
+        assert!(parse("/srv/clients/east", style).specificity(style) > literal);
+    }
+}
@@ -0,0 +1,412 @@
+use crate::config::{AccountConfig, AppConfig, Language};
+use crate::i18n::{t, Key};
+use crate::ops::{ENV_OVERRIDE, GitContext};
+use crate::path_match::{self, PathPattern, PathStyle};
+use globset::GlobBuilder;
+use std::cmp::Reverse;
+use std::path::Path;
+
+/// ルール 1 件の評価結果
+#[derive(Debug, Clone, PartialEq)]
+pub enum RuleOutcome {
+    /// 要求に一致した
+    Matched,
+    /// 要求に一致しなかった
+    NotMatched,
+    /// パターンの構文が不正なため評価できなかった
+    Invalid(String),
+}
+
+/// 評価したルールとその結果
+#[derive(Debug, Clone, PartialEq)]
+pub struct RuleCheck {
+    /// ルールのキー（パスやパターン）
+    pub rule: String,
+    /// ルールが指すアカウントのニックネーム
+    pub account: String,
+    /// 評価結果
+    pub outcome: RuleOutcome,
+}
+
+/// アカウントを決定した根拠
+#[derive(Debug, Clone, PartialEq)]
+pub enum Source {
+    /// `GAS_ACCOUNT_OVERRIDE` による上書き
+    Override,
+    /// `owner_rules` のルール（キー）
+    OwnerRule(String),
+    /// `path_rules` のルール（キー）
+    PathRule(String),
+    /// `default_account`
+    Default,
+}
+
+/// 解決の最終結果
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Outcome {
+    /// 決定したアカウントの資格情報を提供する
+    Use,
+    /// 使用するアカウントが決まらなかった
+    NoAccount,
+    /// 決定したアカウントが登録されていない
+    UnknownAccount,
+    /// 決定したアカウントが要求先のホスト（またはプロトコル）を許可していない
+    HostNotAllowed,
+}
+
+/// アカウント解決の過程と結果をまとめた構造体
+///
+/// `ops::get_credentials` 等が使用するほか、`gas explain` で判定の過程を表示するために使用されます。
+#[derive(Debug, Clone, PartialEq)]
+pub struct Resolution {
+    /// 適用された上書き指定のアカウント
+    pub override_account: Option<String>,
+    /// 評価した `owner_rules`（優先順）
+    pub owner_rules: Vec<RuleCheck>,
+    /// 評価した `path_rules`（優先順）
+    pub path_rules: Vec<RuleCheck>,
+    /// アカウントを決定した根拠
+    pub source: Option<Source>,
+    /// 決定したアカウントのニックネーム
+    pub account: Option<String>,
+    /// 最終結果
+    pub outcome: Outcome,
+}
+
+impl Resolution {
+    /// 資格情報を提供してよい場合に、アカウントのニックネームと設定を返します。
+    pub fn target<'a>(&self, config: &'a AppConfig) -> Option<(String, &'a AccountConfig)> {
+        if self.outcome != Outcome::Use {
+            return None;
+        }
+        let name = self.account.as_ref()?;
+        config.accounts.get(name).map(|account| (name.clone(), account))
+    }
+}
+
+impl RuleCheck {
+    /// `gas explain` 向けにルールの評価結果を1行で表します。
+    pub fn describe(&self, lang: &Language) -> String {
+        let line = |key| t(lang, key).replacen("{}", &self.rule, 1).replacen("{}", &self.account, 1);
+        match &self.outcome {
+            RuleOutcome::Matched => line(Key::ExplainRuleMatched),
+            RuleOutcome::NotMatched => line(Key::ExplainRuleNotMatched),
+            RuleOutcome::Invalid(e) => line(Key::ExplainRuleInvalid).replacen("{}", e, 1),
+        }
+    }
+}
+
+impl Resolution {
+    /// `gas explain` 向けに判定の過程を複数行で表します。
+    pub fn describe(&self, lang: &Language) -> String {
+        let mut lines = Vec::new();
+        lines.push(match &self.override_account {
+            Some(account) => t(lang, Key::ExplainOverrideApplied).replacen("{}", ENV_OVERRIDE, 1).replacen("{}", account, 1),
+            None => t(lang, Key::ExplainOverrideNotSet).replace("{}", ENV_OVERRIDE),
+        });
+        for (title, checks, winner) in [
+            (Key::ExplainOwnerRules, &self.owner_rules, self.source.as_ref().and_then(|s| match s { Source::OwnerRule(r) => Some(r), _ => None })),
+            (Key::ExplainPathRules, &self.path_rules, self.source.as_ref().and_then(|s| match s { Source::PathRule(r) => Some(r), _ => None })),
+        ] {
+            lines.push(t(lang, title).to_string());
+            if checks.is_empty() {
+                lines.push(format!("  {}", t(lang, Key::ExplainNoRules)));
+            }
+            for check in checks {
+                let mark = if winner == Some(&check.rule) { t(lang, Key::ExplainSelected) } else { "" };
+                lines.push(format!("  {}{}", check.describe(lang), mark));
+            }
+        }
+        let source = match &self.source {
+            Some(Source::Override) => t(lang, Key::ExplainByOverride).to_string(),
+            Some(Source::OwnerRule(rule)) => t(lang, Key::ExplainByOwnerRule).replace("{}", rule),
+            Some(Source::PathRule(rule)) => t(lang, Key::ExplainByPathRule).replace("{}", rule),
+            Some(Source::Default) => t(lang, Key::ExplainByDefault).to_string(),
+            None => t(lang, Key::ExplainByNothing).to_string(),
+        };
+        let account = self.account.as_deref().unwrap_or("-");
+        let result = match self.outcome {
+            Outcome::Use => Key::ExplainResultUse,
+            Outcome::NoAccount => Key::ExplainResultNoAccount,
+            Outcome::UnknownAccount => Key::ExplainResultUnknownAccount,
+            Outcome::HostNotAllowed => Key::ExplainResultHostNotAllowed,
+        };
+        lines.push(t(lang, result).replacen("{}", account, 1).replacen("{}", &source, 1));
+        lines.join("\n")
+    }
+}
+
+/// Git の要求に対して使用するアカウントを決定します。キーリング等には一切アクセスしません。
+///
+/// `override_account` が指定されていればそれを優先し、次にリモートのホストと所有者に一致する `owner_rules`、
+/// `path_rules` のうち一致したルール、最後にデフォルトのアカウントを使用します。
+/// 複数のルールが一致した場合は、より具体的なルール（`PathPattern::specificity`）、
+/// 次に `priority` の大きいルールを優先します。それでも並ぶ場合はキーの辞書順で決定します。
+/// 判定の過程を表示できるよう、上書き指定の有無に関わらずすべてのルールを評価します。
+///
+/// # Arguments
+/// * `config` - アプリケーション設定
+/// * `ctx` - Git から渡された要求
+/// * `current_dir` - 現在のディレクトリパス
+/// * `override_account` - 環境変数等で明示的に指定されたアカウントニックネーム
+pub fn resolve(
+    config: &AppConfig,
+    ctx: &GitContext,
+    current_dir: &str,
+    override_account: Option<&str>,
+) -> Resolution {
+    let owner_rules = check_owner_rules(config, ctx);
+    let path_rules = check_path_rules(config, current_dir);
+    let first_match = |checks: &[RuleCheck]| {
+        checks.iter().find(|c| c.outcome == RuleOutcome::Matched).map(|c| (c.rule.clone(), c.account.clone()))
+    };
+
+    let (source, account) = if let Some(account) = override_account {
+        (Some(Source::Override), Some(account.to_string()))
+    } else if let Some((rule, account)) = first_match(&owner_rules) {
+        (Some(Source::OwnerRule(rule)), Some(account))
+    } else if let Some((rule, account)) = first_match(&path_rules) {
+        (Some(Source::PathRule(rule)), Some(account))
+    } else if let Some(account) = &config.default_account {
+        (Some(Source::Default), Some(account.clone()))
+    } else {
+        (None, None)
+    };
+
+    let outcome = match account.as_ref().map(|name| config.accounts.get(name)) {
+        None => Outcome::NoAccount,
+        Some(None) => Outcome::UnknownAccount,
+        Some(Some(c)) if !c.allows(&ctx.protocol, &ctx.host) => Outcome::HostNotAllowed,
+        Some(Some(_)) => Outcome::Use,
+    };
+
+    Resolution {
+        override_account: override_account.map(str::to_string),
+        owner_rules,
+        path_rules,
+        source,
+        account,
+        outcome,
+    }
+}
+
+/// `path_rules` を評価し、優先順（一致したものを先頭）に並べて返します。
+///
+/// 相対パスのキーは、`includeIf` の生成と同じくホームディレクトリ（`path_match::rule_base`）を基準に解決します。
+fn check_path_rules(config: &AppConfig, current_dir: &str) -> Vec<RuleCheck> {
+    let style = PathStyle::native();
+    let current = path_match::canonicalize(current_dir, Path::new(current_dir));
+    let base = path_match::rule_base();
+
+    let mut checks: Vec<_> = config.path_rules.iter()
+        .map(|(key, rule)| {
+            let (outcome, rank) = match PathPattern::parse(key, &base, style) {
+                Ok(pattern) if pattern.matches(&current, style) => {
+                    (RuleOutcome::Matched, Some((pattern.specificity(style), rule.priority())))
+                }
+                Ok(_) => (RuleOutcome::NotMatched, None),
+                Err(e) => (RuleOutcome::Invalid(format!("{:#}", e)), None),
+            };
+            (rank, RuleCheck { rule: key.clone(), account: rule.account().to_string(), outcome })
+        })
+        .collect();
+    checks.sort_by(|(ra, a), (rb, b)| rb.cmp(ra).then_with(|| a.rule.cmp(&b.rule)));
+    checks.into_iter().map(|(_, check)| check).collect()
+}
+
+/// `owner_rules` を評価し、優先順（一致したものを先頭）に並べて返します。
+///
+/// ルールは要求先のホストとリポジトリパス（末尾の `.git` を除いた `host/owner/repo`）に対して、
+/// ホスト・所有者・リポジトリを `/` で区切ったグロブとして大文字・小文字を区別せずに照合されます。
+/// `github.com/acme-corp` のようにワイルドカードを含まないルールは、その配下のリポジトリすべてに一致します。
+/// 複数一致した場合は固定部分の多いルールを優先します。
+/// リポジトリパスは `credential.useHttpPath` が有効な場合にのみ Git から渡されるため、
+/// パスがない場合はすべて不一致となります。
+fn check_owner_rules(config: &AppConfig, ctx: &GitContext) -> Vec<RuleCheck> {
+    let target = ctx.path.as_deref().map(|path| {
+        let path = path.trim_matches('/');
+        format!("{}/{}", ctx.host, path.strip_suffix(".git").unwrap_or(path)).to_lowercase()
+    });
+
+    let mut checks: Vec<_> = config.owner_rules.iter()
+        .map(|(pattern, account)| {
+            let outcome = match &target {
+                Some(target) => owner_rule_outcome(pattern, target),
+                None => RuleOutcome::NotMatched,
+            };
+            let rank = (outcome == RuleOutcome::Matched).then(|| owner_rule_depth(pattern));
+            (rank, RuleCheck { rule: pattern.clone(), account: account.clone(), outcome })
+        })
+        .collect();
+    checks.sort_by_key(|(rank, check)| (Reverse(*rank), check.rule.clone()));
+    checks.into_iter().map(|(_, check)| check).collect()
+}
+
+fn owner_rule_outcome(pattern: &str, target: &str) -> RuleOutcome {
+    let pattern = pattern.trim_matches('/').to_lowercase();
+    let matched = if !pattern.contains(['*', '?', '[', '{']) {
+        target == pattern || target.starts_with(&format!("{}/", pattern))
+    } else {
+        match GlobBuilder::new(&pattern).literal_separator(true).build() {
+            Ok(glob) => glob.compile_matcher().is_match(target),
+            Err(e) => return RuleOutcome::Invalid(e.to_string()),
+        }
+    };
+    if matched { RuleOutcome::Matched } else { RuleOutcome::NotMatched }
+}
+
+fn owner_rule_depth(pattern: &str) -> usize {
+    pattern.split('/')
+        .filter(|c| !c.is_empty())
+        .take_while(|c| !c.contains(['*', '?', '[', '{']))
+        .count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::PathRule;
+    use crate::ops::parse_git_input;
+
+    fn account_of(config: &AppConfig, input: &str, dir: &str) -> Option<String> {
+        resolve(config, &parse_git_input(input), dir, None).account
+    }
+
+    #[test]
+    fn test_resolve_prefers_deepest_path_rule() {
+        let dir = tempfile::tempdir().unwrap();
+        let base = std::fs::canonicalize(dir.path()).unwrap();
+        let work = base.join("work");
+        let client = work.join("client");
+        std::fs::create_dir_all(&client).unwrap();
+
+        let mut config = AppConfig { default_account: Some("Home".into()), ..Default::default() };
+        config.path_rules.insert(work.to_string_lossy().into(), "Work".into());
+        config.path_rules.insert(client.to_string_lossy().into(), "Client".into());
+
+        let resolve_dir = |dir: &Path| account_of(&config, "", &dir.to_string_lossy());
+        assert_eq!(resolve_dir(&client.join("repo")).as_deref(), Some("Client"));
+        assert_eq!(resolve_dir(&work.join("repo")).as_deref(), Some("Work"));
+        // 構成要素の途中では一致しない
+        assert_eq!(resolve_dir(&base.join("workshop")).as_deref(), Some("Home"));
+    }
+
+    #[test]
+    fn test_relative_path_rule_is_based_on_home() {
+        let home = path_match::rule_base();
+        let mut config = AppConfig { default_account: Some("Home".into()), ..Default::default() };
+        config.path_rules.insert("gas-test-missing/work".into(), "Work".into());
+
+        let inside = home.join("gas-test-missing").join("work").join("repo");
+        assert_eq!(account_of(&config, "", &inside.to_string_lossy()).as_deref(), Some("Work"));
+        // 現在のディレクトリを基準にしない
+        let elsewhere = home.join("elsewhere");
+        let relative_to_cwd = elsewhere.join("gas-test-missing").join("work");
+        assert_eq!(account_of(&config, "", &relative_to_cwd.to_string_lossy()).as_deref(), Some("Home"));
+    }
+
+    #[test]
+    fn test_resolve_with_patterns_and_priority() {
+        let dir = tempfile::tempdir().unwrap();
+        let base = std::fs::canonicalize(dir.path()).unwrap();
+        let base_str = base.to_string_lossy().replace('\\', "/");
+        let repo = base.join("clients").join("east").join("acme");
+        std::fs::create_dir_all(&repo).unwrap();
+
+        let mut config = AppConfig::default();
+        config.path_rules.insert(format!("{}/clients/*/acme", base_str), "Glob".into());
+        config.path_rules.insert(format!("re:^{}/clients/[^/]+/acme", regex::escape(&base_str)), "Regex".into());
+        let resolve_repo = |config: &AppConfig| account_of(config, "", &repo.to_string_lossy());
+
+        // 深さが同じ場合はグロブが正規表現より優先される
+        assert_eq!(resolve_repo(&config).as_deref(), Some("Glob"));
+
+        // 具体性が同じルール同士では priority の大きい方が優先される
+        config.path_rules.insert(format!("{}/clients/*/ac*", base_str), PathRule::Detailed { account: "Priority".into(), priority: 10 });
+        assert_eq!(resolve_repo(&config).as_deref(), Some("Priority"));
+
+        // より具体的なルールは priority に関係なく優先される
+        config.path_rules.insert(format!("{}/clients/east", base_str), "Literal".into());
+        assert_eq!(resolve_repo(&config).as_deref(), Some("Literal"));
+    }
+
+    #[test]
+    fn test_owner_rule_overrides_path_rule() {
+        let mut config = AppConfig { default_account: Some("Home".into()), ..Default::default() };
+        config.path_rules.insert("/".into(), "Home".into());
+        config.owner_rules.insert("github.com/acme-corp/*".into(), "Work".into());
+        config.owner_rules.insert("github.com/acme-corp/secret-repo".into(), "Admin".into());
+
+        let resolve_input = |input: &str| account_of(&config, input, "/tmp");
+        assert_eq!(resolve_input("protocol=https\nhost=github.com\npath=Acme-Corp/app.git\n").as_deref(), Some("Work"));
+        assert_eq!(resolve_input("protocol=https\nhost=github.com\npath=acme-corp/secret-repo.git\n").as_deref(), Some("Admin"));
+        assert_eq!(resolve_input("protocol=https\nhost=github.com\npath=someone/app.git\n").as_deref(), Some("Home"));
+        // パスが渡されない場合（useHttpPath 無効）は所有者ルールを使えない
+        assert_eq!(resolve_input("protocol=https\nhost=github.com\n").as_deref(), Some("Home"));
+        // ホストが異なる場合は一致しない
+        assert_eq!(resolve_input("protocol=https\nhost=gitlab.com\npath=acme-corp/app.git\n").as_deref(), Some("Home"));
+    }
+
+    #[test]
+    fn test_owner_rule_without_wildcard_matches_owner() {
+        let mut config = AppConfig::default();
+        config.owner_rules.insert("github.com/acme-corp".into(), "Work".into());
+
+        assert_eq!(account_of(&config, "host=github.com\npath=acme-corp/app.git\n", "/tmp").as_deref(), Some("Work"));
+        assert_eq!(account_of(&config, "host=github.com\npath=acme-corporate/app.git\n", "/tmp"), None);
+    }
+
+    #[test]
+    fn test_resolution_records_override_and_rule_checks() {
+        let mut config = AppConfig { default_account: Some("Home".into()), ..Default::default() };
+        config.accounts.insert("Home".into(), AccountConfig::new("homeuser"));
+        config.owner_rules.insert("github.com/acme-corp/*".into(), "Work".into());
+        config.path_rules.insert("re:(".into(), "Broken".into());
+        let ctx = parse_git_input("protocol=https\nhost=github.com\npath=acme-corp/app.git\n");
+
+        let resolution = resolve(&config, &ctx, "/tmp", Some("Home"));
+        assert_eq!(resolution.override_account.as_deref(), Some("Home"));
+        assert_eq!(resolution.source, Some(Source::Override));
+        assert_eq!(resolution.outcome, Outcome::Use);
+        // 上書き時もルールは評価される
+        assert_eq!(resolution.owner_rules[0].outcome, RuleOutcome::Matched);
+        assert!(matches!(resolution.path_rules[0].outcome, RuleOutcome::Invalid(_)));
+
+        let resolution = resolve(&config, &ctx, "/tmp", None);
+        assert_eq!(resolution.source, Some(Source::OwnerRule("github.com/acme-corp/*".into())));
+        assert_eq!(resolution.outcome, Outcome::UnknownAccount);
+        assert!(resolution.target(&config).is_none());
+    }
+
+    #[test]
+    fn test_resolution_rejects_unlisted_host() {
+        let mut config = AppConfig { default_account: Some("Home".into()), ..Default::default() };
+        config.accounts.insert("Home".into(), AccountConfig::new("homeuser"));
+
+        let resolution = resolve(&config, &parse_git_input("protocol=https\nhost=gitea.internal\n"), "/tmp", None);
+        assert_eq!(resolution.source, Some(Source::Default));
+        assert_eq!(resolution.outcome, Outcome::HostNotAllowed);
+
+        let resolution = resolve(&AppConfig::default(), &parse_git_input("host=github.com\n"), "/tmp", None);
+        assert_eq!(resolution.outcome, Outcome::NoAccount);
+    }
+
+    #[test]
+    fn test_resolution_display_marks_selected_rule() {
+        let mut config = AppConfig::default();
+        config.accounts.insert("Work".into(), AccountConfig::new("workuser"));
+        config.owner_rules.insert("github.com/acme-corp/*".into(), "Work".into());
+        config.owner_rules.insert("github.com/other/*".into(), "Other".into());
+
+        let ctx = parse_git_input("protocol=https\nhost=github.com\npath=acme-corp/app.git\n");
+        let text = resolve(&config, &ctx, "/tmp", None).describe(&Language::En);
+        assert!(text.contains("GAS_ACCOUNT_OVERRIDE: not set"));
+        assert!(text.contains("[match]    github.com/acme-corp/* -> Work  <= selected"));
+        assert!(text.contains("[no match] github.com/other/* -> Other"));
+        assert!(text.ends_with("Result: account 'Work' (selected by owner rule 'github.com/acme-corp/*')"));
+
+        let text = resolve(&config, &ctx, "/tmp", None).describe(&Language::Ja);
+        assert!(text.contains("[一致]   github.com/acme-corp/* -> Work  <= 採用"));
+        assert!(text.ends_with("結果: アカウント 'Work'（所有者ルール 'github.com/acme-corp/*' により決定）"));
+    }
+}
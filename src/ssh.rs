@@ -0,0 +1,178 @@
+use crate::config::{AppConfig, safe_name};
+use anyhow::{Context, Result, bail};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// `~/.ssh/config` 内の管理ブロックの開始行
+const BLOCK_BEGIN: &str = "# >>> gas managed block >>>";
+/// `~/.ssh/config` 内の管理ブロックの終了行
+const BLOCK_END: &str = "# <<< gas managed block <<<";
+
+/// ホスト名とアカウントのニックネームから SSH のホストエイリアス（`github.com-<nickname>`）を作成します。
+pub fn host_alias(host: &str, nickname: &str) -> String {
+    format!("{}-{}", host, safe_name(nickname))
+}
+
+/// アカウント用の鍵の既定の保存先（`~/.ssh/gas_<nickname>_ed25519`）を返します。
+///
+/// # Errors
+/// ホームディレクトリを特定できない場合にエラーを返します。
+pub fn default_key_path(nickname: &str) -> Result<PathBuf> {
+    let home = dirs::home_dir().context("Could not determine home directory")?;
+    Ok(home.join(".ssh").join(format!("gas_{}_ed25519", safe_name(nickname))))
+}
+
+/// パスを OpenSSH / Git の設定で扱える形（区切り文字 `/`）に変換します。
+fn ssh_path(path: &Path) -> String {
+    path.to_string_lossy().replace('\\', "/")
+}
+
+/// 指定した秘密鍵のみを使用する `ssh` コマンド（`core.sshCommand` / `GIT_SSH_COMMAND` 用）を返します。
+pub fn ssh_command(key: &Path) -> String {
+    format!("ssh -i \"{}\" -o IdentitiesOnly=yes", ssh_path(key))
+}
+
+/// `ssh-keygen` で ed25519 の鍵を生成します。パスフレーズは `ssh-keygen` が対話的に尋ねます。
+///
+/// # Arguments
+/// * `path` - 秘密鍵の保存先（公開鍵は `.pub` を付けたパスに保存されます）
+/// * `comment` - 鍵のコメント（メールアドレス等）
+///
+/// # Errors
+/// 鍵がすでに存在する場合や、`ssh-keygen` の実行に失敗した場合にエラーを返します。
+pub fn generate_key(path: &Path, comment: &str) -> Result<()> {
+    if path.exists() {
+        bail!("SSH key already exists: {}", path.display());
+    }
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let status = Command::new("ssh-keygen")
+        .args(["-t", "ed25519", "-C", comment, "-f"])
+        .arg(path)
+        .status()
+        .context("Failed to execute ssh-keygen.")?;
+    if !status.success() { bail!("ssh-keygen failed"); }
+    Ok(())
+}
+
+/// SSH 鍵を持つアカウントについて、ホストごとの `Host <host>-<nickname>` エントリーを生成します。
+pub fn render_block(config: &AppConfig) -> String {
+    let mut accounts: Vec<_> = config.accounts.iter()
+        .filter_map(|(nickname, account)| account.ssh_key.as_ref().map(|key| (nickname, account, key)))
+        .collect();
+    accounts.sort_by_key(|(nickname, _, _)| *nickname);
+
+    let mut out = format!("{}\n", BLOCK_BEGIN);
+    for (nickname, account, key) in accounts {
+        for host in &account.hosts {
+            // SSH のホスト名にはポート番号を含めない
+            let host = host.split(':').next().unwrap_or(host);
+            out.push_str(&format!("Host {}\n", host_alias(host, nickname)));
+            out.push_str(&format!("    HostName {}\n", host));
+            out.push_str("    User git\n");
+            out.push_str(&format!("    IdentityFile \"{}\"\n", ssh_path(key)));
+            out.push_str("    IdentitiesOnly yes\n");
+        }
+    }
+    out.push_str(BLOCK_END);
+    out.push('\n');
+    out
+}
+
+/// `~/.ssh/config` の内容のうち管理ブロックのみを `block` で置き換えます。
+///
+/// 管理ブロックがない場合は末尾に追加し、ブロック外の内容は変更しません。
+pub fn replace_block(content: &str, block: &str) -> String {
+    if let Some(start) = content.find(BLOCK_BEGIN)
+        && let Some(end_rel) = content[start..].find(BLOCK_END)
+    {
+        let mut end = start + end_rel + BLOCK_END.len();
+        if content[end..].starts_with('\n') {
+            end += 1;
+        }
+        return format!("{}{}{}", &content[..start], block, &content[end..]);
+    }
+    if content.is_empty() {
+        return block.to_string();
+    }
+    let separator = if content.ends_with('\n') { "\n" } else { "\n\n" };
+    format!("{}{}{}", content, separator, block)
+}
+
+/// `~/.ssh/config` の管理ブロックを現在のアカウント設定に合わせて更新します。
+///
+/// SSH 鍵を持つアカウントがなく、管理ブロックもまだない場合はファイルを作成・変更しません。
+///
+/// # Errors
+/// ファイルの読み書きに失敗した場合にエラーを返します。
+pub fn sync_ssh_config(config: &AppConfig) -> Result<()> {
+    let ssh_dir = dirs::home_dir().context("Could not determine home directory")?.join(".ssh");
+    let path = ssh_dir.join("config");
+    let content = fs::read_to_string(&path).unwrap_or_default();
+    let has_keys = config.accounts.values().any(|a| a.ssh_key.is_some());
+    if !has_keys && !content.contains(BLOCK_BEGIN) {
+        return Ok(());
+    }
+    let updated = replace_block(&content, &render_block(config));
+    if updated != content {
+        if !ssh_dir.exists() {
+            fs::create_dir_all(&ssh_dir)?;
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::PermissionsExt;
+                fs::set_permissions(&ssh_dir, fs::Permissions::from_mode(0o700))?;
+            }
+        }
+        fs::write(&path, updated).context("Failed to write ssh config")?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::AccountConfig;
+
+    fn config_with_key() -> AppConfig {
+        let mut config = AppConfig::default();
+        config.accounts.insert("Work".into(), AccountConfig {
+            ssh_key: Some(PathBuf::from("/home/me/.ssh/gas_Work_ed25519")),
+            ..AccountConfig::new("workuser")
+        });
+        config.accounts.insert("Home".into(), AccountConfig::new("homeuser"));
+        config
+    }
+
+    #[test]
+    fn test_ssh_command() {
+        assert_eq!(ssh_command(Path::new("/k/id")), "ssh -i \"/k/id\" -o IdentitiesOnly=yes");
+    }
+
+    #[test]
+    fn test_render_block() {
+        let block = render_block(&config_with_key());
+        assert!(block.starts_with(BLOCK_BEGIN));
+        assert!(block.contains("Host github.com-Work\n    HostName github.com\n    User git\n    IdentityFile \"/home/me/.ssh/gas_Work_ed25519\"\n"));
+        assert!(!block.contains("Home"));
+    }
+
+    #[test]
+    fn test_replace_block_keeps_unmanaged_entries() {
+        let block = render_block(&config_with_key());
+        let original = "Host example\n    User me\n";
+        let first = replace_block(original, &block);
+        assert!(first.starts_with(original));
+        assert!(first.ends_with(&block));
+
+        // 再実行しても内容は変わらない
+        assert_eq!(replace_block(&first, &block), first);
+
+        // 管理ブロックのみが置き換わる
+        let with_trailer = format!("{}Host after\n", first);
+        let empty = render_block(&AppConfig::default());
+        let replaced = replace_block(&with_trailer, &empty);
+        assert_eq!(replaced, format!("{}\n{}Host after\n", original, empty));
+    }
+}
@@ -0,0 +1,290 @@
+use crate::auth::AuthProvider;
+use crate::config::{AccountConfig, AppConfig, Language};
+use crate::credential::{CredentialStore, SecretKind};
+use crate::expiry;
+use crate::i18n::{t, Key};
+use anyhow::Result;
+
+/// 検証する秘密情報の種類（リフレッシュトークンは API の認証に使用できず、
+/// レジストリのトークンは Git ホストの API では検証できないため対象外）
+const KINDS: [SecretKind; 2] = [SecretKind::OAuth, SecretKind::Pat];
+
+/// 1つの秘密情報の検証結果
+#[derive(Debug, Clone, PartialEq)]
+pub enum Status {
+    /// プロバイダーが受け付け、ログイン名もアカウントのユーザー名と一致した（付与されたスコープ）
+    Valid { scopes: Option<Vec<String>> },
+    /// プロバイダーは受け付けたが、ログイン名がアカウントのユーザー名と一致しない
+    WrongUser { login: String, expected: String },
+    /// 有効だが、付与されたスコープが `gas add` で要求したスコープと異なる（不足しているもの・余分なもの）
+    ScopeDrift { missing: Vec<String>, extra: Vec<String> },
+    /// プロバイダーが拒否した
+    Invalid,
+    /// プロバイダーが拒否し、記録された有効期限も過ぎている（有効期限の UNIX 時間）
+    Expired(u64),
+    /// 秘密情報が1つも保存されていない
+    Missing,
+    /// 検証できなかった（理由）
+    Error(String),
+}
+
+/// アカウントの秘密情報ごとの検証結果
+#[derive(Debug, Clone, PartialEq)]
+pub struct Report {
+    /// 表示ラベル（PAT ではニックネーム、それ以外の種類では `<nickname> (<kind>)`）
+    pub label: String,
+    /// 検証結果
+    pub status: Status,
+}
+
+impl Report {
+    /// 問題が見つかったかどうかを返します。
+    pub fn is_problem(&self) -> bool {
+        !matches!(self.status, Status::Valid { .. })
+    }
+}
+
+impl Report {
+    /// `gas verify` 向けに検証結果を1行で表します。
+    pub fn describe(&self, lang: &Language) -> String {
+        let line = |key| t(lang, key).replacen("{}", &self.label, 1);
+        match &self.status {
+            Status::Valid { scopes: Some(scopes) } => line(Key::VerifyValidScopes).replacen("{}", &scopes.join(", "), 1),
+            Status::Valid { scopes: None } => line(Key::VerifyValid),
+            Status::WrongUser { login, expected } => line(Key::VerifyWrongUser).replacen("{}", login, 1).replacen("{}", expected, 1),
+            Status::ScopeDrift { missing, extra } => {
+                let mut text = line(Key::VerifyScopeDrift);
+                if !missing.is_empty() {
+                    text += &format!(" - {}", t(lang, Key::VerifyScopesMissing).replace("{}", &missing.join(", ")));
+                }
+                if !extra.is_empty() {
+                    text += &format!(" - {}", t(lang, Key::VerifyScopesExtra).replace("{}", &extra.join(", ")));
+                }
+                text
+            }
+            Status::Invalid => line(Key::VerifyInvalid),
+            Status::Expired(at) => line(Key::VerifyExpired).replacen("{}", &expiry::format_date(*at), 1),
+            Status::Missing => line(Key::VerifyMissing),
+            Status::Error(reason) => line(Key::VerifyError).replacen("{}", reason, 1),
+        }
+    }
+}
+
+/// 要求したスコープと付与されたスコープを比較し、差異があれば `ScopeDrift` を返します。
+fn scope_drift(requested: &[String], granted: &[String]) -> Option<Status> {
+    let missing: Vec<String> = requested.iter().filter(|s| !granted.contains(s)).cloned().collect();
+    let extra: Vec<String> = granted.iter().filter(|s| !requested.contains(s)).cloned().collect();
+    if missing.is_empty() && extra.is_empty() { None } else { Some(Status::ScopeDrift { missing, extra }) }
+}
+
+/// 1つの秘密情報をプロバイダーの API で検証します。
+///
+/// OAuth トークンは、アカウントに要求したスコープが記録されていればプロバイダーが返したスコープと比較します。
+fn verify_secret(provider: &dyn AuthProvider, account: &AccountConfig, kind: SecretKind, secret: &str, now: u64) -> Status {
+    match provider.validate(Some(&account.username), secret) {
+        Ok(Some(info)) if info.identity.login.eq_ignore_ascii_case(&account.username) => match &info.scopes {
+            Some(granted) if kind == SecretKind::OAuth && !account.scopes.is_empty() => {
+                scope_drift(&account.scopes, granted).unwrap_or(Status::Valid { scopes: info.scopes })
+            }
+            _ => Status::Valid { scopes: info.scopes },
+        },
+        Ok(Some(info)) => Status::WrongUser { login: info.identity.login, expected: account.username.clone() },
+        Ok(None) => match account.secrets.get(&kind).and_then(|meta| meta.expires_at) {
+            Some(at) if expiry::status(Some(at), now) == expiry::Status::Expired => Status::Expired(at),
+            _ => Status::Invalid,
+        },
+        Err(e) => Status::Error(format!("{:#}", e)),
+    }
+}
+
+/// 1つのアカウントに保存されたすべての種類の秘密情報を検証します。
+///
+/// `provider` はアカウントの最初のホストのプロバイダーです。取得できなかった場合はその理由を各結果に記録します。
+pub fn verify_account(
+    nickname: &str,
+    account: &AccountConfig,
+    store: &impl CredentialStore,
+    provider: Result<&dyn AuthProvider, String>,
+    now: u64,
+) -> Vec<Report> {
+    let mut reports = Vec::new();
+    for kind in KINDS {
+        let Ok(secret) = store.get(nickname, kind) else { continue };
+        let status = match &provider {
+            Ok(provider) => verify_secret(*provider, account, kind, &secret, now),
+            Err(reason) => Status::Error(reason.clone()),
+        };
+        let label = match kind {
+            SecretKind::Pat => nickname.to_string(),
+            kind => format!("{} ({})", nickname, kind),
+        };
+        reports.push(Report { label, status });
+    }
+    if reports.is_empty() {
+        reports.push(Report { label: nickname.to_string(), status: Status::Missing });
+    }
+    reports
+}
+
+/// 指定したアカウントを名前順に検証します。
+///
+/// プロバイダーは各アカウントの最初のホスト（未設定の場合は `github.com`）から `provider_for` で作成します。
+pub fn run(
+    config: &AppConfig,
+    store: &impl CredentialStore,
+    names: &[String],
+    provider_for: impl Fn(&str) -> Result<Box<dyn AuthProvider>>,
+) -> Vec<Report> {
+    let mut names = names.to_vec();
+    names.sort();
+    let now = expiry::now();
+    let mut reports = Vec::new();
+    for name in names {
+        let Some(account) = config.accounts.get(&name) else { continue };
+        let host = account.hosts.first().map_or("github.com", String::as_str);
+        let provider = provider_for(host);
+        let provider = match &provider {
+            Ok(provider) => Ok(provider.as_ref()),
+            Err(e) => Err(format!("{:#}", e)),
+        };
+        reports.extend(verify_account(&name, account, store, provider, now));
+    }
+    reports
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::{AccessToken, DeviceAuthorization, Flow, Identity, TokenInfo};
+    use crate::config::SecretMeta;
+    use crate::credential::MockStore;
+    use anyhow::bail;
+
+    /// トークンの値に応じて検証結果を返すプロバイダー
+    struct FakeProvider;
+
+    impl AuthProvider for FakeProvider {
+        fn name(&self) -> &'static str {
+            "Fake"
+        }
+
+        fn start(&self) -> Result<Flow> {
+            bail!("unused")
+        }
+
+        fn poll(&self, _device: &DeviceAuthorization) -> Result<AccessToken> {
+            bail!("unused")
+        }
+
+        fn identity(&self, _login: Option<&str>, _secret: &str) -> Result<Identity> {
+            bail!("unused")
+        }
+
+        fn validate(&self, _login: Option<&str>, secret: &str) -> Result<Option<TokenInfo>> {
+            let login = match secret {
+                "work-token" => "WorkUser",
+                "other-token" => "someone-else",
+                "offline" => bail!("connection refused"),
+                _ => return Ok(None),
+            };
+            let identity = Identity { login: login.into(), name: None, email: None };
+            Ok(Some(TokenInfo { identity, scopes: Some(vec!["repo".into(), "read:user".into()]) }))
+        }
+
+        fn refresh(&self, _refresh_token: &str) -> Result<AccessToken> {
+            bail!("unused")
+        }
+
+        fn revoke(&self, _secret: &str) -> Result<()> {
+            bail!("unused")
+        }
+
+        fn revocation_url(&self) -> String {
+            "https://example.invalid/settings".to_string()
+        }
+    }
+
+    fn verify(account: &AccountConfig, store: &MockStore) -> Vec<Report> {
+        verify_account("Work", account, store, Ok(&FakeProvider), 1_000)
+    }
+
+    #[test]
+    fn test_verify_reports_each_stored_kind() {
+        let store = MockStore::new();
+        store.set("Work", SecretKind::Pat, "work-token").unwrap();
+        store.set("Work", SecretKind::OAuth, "other-token").unwrap();
+        store.set("Work", SecretKind::RefreshToken, "refresh").unwrap();
+        store.set("Work", SecretKind::Registry, "npm-token").unwrap();
+
+        let reports = verify(&AccountConfig::new("workuser"), &store);
+        assert_eq!(reports.len(), 2);
+        assert_eq!(reports[0].status, Status::WrongUser { login: "someone-else".into(), expected: "workuser".into() });
+        assert_eq!(reports[1].describe(&Language::En), "[ok] Work: valid, scopes: repo, read:user");
+        assert_eq!(reports[1].describe(&Language::Ja), "[ok] Work: 有効、スコープ: repo, read:user");
+    }
+
+    #[test]
+    fn test_verify_flags_scope_drift_of_oauth_token() {
+        let store = MockStore::new();
+        store.set("Work", SecretKind::OAuth, "work-token").unwrap();
+        let mut account = AccountConfig::new("workuser");
+        account.scopes = vec!["repo".into(), "read:user".into()];
+        assert!(!verify(&account, &store)[0].is_problem());
+
+        account.scopes = vec!["repo".into(), "workflow".into()];
+        let report = &verify(&account, &store)[0];
+        assert_eq!(report.status, Status::ScopeDrift { missing: vec!["workflow".into()], extra: vec!["read:user".into()] });
+        assert_eq!(report.describe(&Language::En), "[!!] Work (oauth): valid, but scopes differ from those requested - missing: workflow - extra: read:user");
+        assert_eq!(report.describe(&Language::Ja), "[!!] Work (oauth): 有効ですが、スコープが要求したものと異なります - 不足: workflow - 余分: read:user");
+
+        // PAT は gas が要求したスコープではないため比較しない
+        store.delete("Work", SecretKind::OAuth).unwrap();
+        store.set("Work", SecretKind::Pat, "work-token").unwrap();
+        assert!(!verify(&account, &store)[0].is_problem());
+    }
+
+    #[test]
+    fn test_verify_distinguishes_invalid_and_expired() {
+        let store = MockStore::new();
+        store.set("Work", SecretKind::Pat, "revoked").unwrap();
+        store.set("Work", SecretKind::OAuth, "expired").unwrap();
+        let mut account = AccountConfig::new("workuser");
+        account.secrets.insert(SecretKind::OAuth, SecretMeta { issued_at: None, expires_at: Some(500) });
+
+        let reports = verify(&account, &store);
+        assert_eq!(reports[0].status, Status::Expired(500));
+        assert_eq!(reports[1].status, Status::Invalid);
+        assert!(reports.iter().all(Report::is_problem));
+    }
+
+    #[test]
+    fn test_verify_reports_missing_and_errors() {
+        let store = MockStore::new();
+        assert_eq!(verify(&AccountConfig::new("workuser"), &store)[0].status, Status::Missing);
+
+        store.set("Work", SecretKind::Pat, "offline").unwrap();
+        let reports = verify(&AccountConfig::new("workuser"), &store);
+        assert!(reports[0].describe(&Language::En).contains("could not verify - connection refused"));
+
+        let reports = verify_account("Work", &AccountConfig::new("workuser"), &store, Err("no client_id".into()), 1_000);
+        assert_eq!(reports[0].status, Status::Error("no client_id".into()));
+    }
+
+    #[test]
+    fn test_run_uses_first_host_and_sorts() {
+        let mut config = AppConfig::default();
+        config.accounts.insert("Work".into(), AccountConfig { hosts: vec!["gitlab.example.com".into()], ..AccountConfig::new("workuser") });
+        config.accounts.insert("Home".into(), AccountConfig::new("homeuser"));
+        let store = MockStore::new();
+        store.set("Work", SecretKind::Pat, "work-token").unwrap();
+
+        let names = vec!["Work".to_string(), "Home".to_string()];
+        let reports = run(&config, &store, &names, |host| {
+            assert!(host == "gitlab.example.com" || host == "github.com");
+            Ok(Box::new(FakeProvider))
+        });
+        assert_eq!(reports.iter().map(|r| r.label.as_str()).collect::<Vec<_>>(), ["Home", "Work"]);
+        assert_eq!(reports[0].status, Status::Missing);
+        assert!(!reports[1].is_problem());
+    }
+}
@@ -4,7 +4,7 @@ use predicates::prelude::*;
 
 #[test]
 fn test_cli_help() {
-    let mut cmd = Command::cargo_bin("gas").unwrap();
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("gas"));
     cmd.arg("--help")
         .assert()
         .success()
@@ -13,7 +13,7 @@ fn test_cli_help() {
 
 #[test]
 fn test_cli_version() {
-    let mut cmd = Command::cargo_bin("gas").unwrap();
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("gas"));
     cmd.arg("--version")
         .assert()
         .success()
@@ -22,9 +22,22 @@ fn test_cli_version() {
 
 #[test]
 fn test_cli_list_no_accounts_hint() {
-    let mut cmd = Command::cargo_bin("gas").unwrap();
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("gas"));
     cmd.arg("list")
         .assert()
         .success()
         .stderr(predicate::str::contains("--- Accounts ---"));
 }
+
+#[test]
+fn test_cli_explain_without_config() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("gas"));
+    cmd.args(["explain", "--host", "github.com", "--path", "acme/app.git"])
+        .env("XDG_CONFIG_HOME", dir.path())
+        .env_remove("GAS_ACCOUNT_OVERRIDE")
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("GAS_ACCOUNT_OVERRIDE: not set"))
+        .stderr(predicate::str::contains("Result: no account"));
+}